@@ -7,6 +7,7 @@ use std::io;
 use std::io::{BufRead, Read, Write};
 use std::net::TcpStream;
 use std::string::FromUtf8Error;
+use types::Type;
 
 const NETWORK_BUFFER_SIZE: usize = 1024;
 
@@ -49,14 +50,14 @@ fn main() -> io::Result<()> {
             1 => println!("{:?}", buffer.content_as_string()),
             // inserts, updates or deletes
             2 => println!("{:?}", buffer.content_as_string()),
-            // select
+            // select, sent in frames until one is not continued
             3 => {
-                println!("{:?}", buffer.content_as_string());
-                // for _ in 0..size {
-                // let value: Result<Int, Box<ErrorKind>> = bincode::deserialize(&buffer[1..len]);
-
-                // }
+                for row in buffer.rows()? {
+                    println!("{:?}", row);
+                }
             }
+            // query plan
+            4 => println!("{:?}", buffer.content_as_string()),
             code => {
                 println!("Unknown server code {:?}. Exiting!", code);
                 break;
@@ -91,9 +92,12 @@ impl<S: Read + Write> Buffer<S> {
     }
 
     pub fn wait(&mut self) -> io::Result<()> {
-        let result = self.source.read(self.bytes.borrow_mut()).map(|len| {
-            self.length += len;
-        });
+        let result = self
+            .source
+            .read(self.bytes[self.length..].borrow_mut())
+            .map(|len| {
+                self.length += len;
+            });
         trace!(
             "Received from server {:?}",
             self.bytes[self.consumed..self.length].borrow()
@@ -105,7 +109,7 @@ impl<S: Read + Write> Buffer<S> {
         if self.length == self.consumed {
             self.wait();
         }
-        let code = self.bytes[0];
+        let code = self.bytes[self.consumed];
         self.consumed += 1;
         if self.length == self.consumed {
             self.length = 0;
@@ -123,4 +127,85 @@ impl<S: Read + Write> Buffer<S> {
         self.consumed = 0;
         str
     }
+
+    /// reads the rows of a select result, which follow its first server code,
+    /// from frames of `[continued, rows count, rows...]` and the server code of every next frame
+    pub fn rows(&mut self) -> io::Result<Vec<Vec<Type>>> {
+        let mut rows = vec![];
+        loop {
+            let continued = self.server_code();
+            let count = self.server_code();
+            for _ in 0..count {
+                let row = bincode::deserialize_from(&mut *self)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                rows.push(row);
+            }
+            if continued == 0 {
+                return Ok(rows);
+            }
+            self.server_code();
+        }
+    }
+}
+
+impl<S: Read + Write> Read for Buffer<S> {
+    fn read(&mut self, bytes: &mut [u8]) -> io::Result<usize> {
+        if self.length == self.consumed {
+            self.length = 0;
+            self.consumed = 0;
+            self.wait()?;
+        }
+        let len = bytes.len().min(self.length - self.consumed);
+        bytes[..len].copy_from_slice(&self.bytes[self.consumed..self.consumed + len]);
+        self.consumed += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn frame(continued: u8, rows: &[Vec<Type>]) -> Vec<u8> {
+        let mut frame = vec![3, continued, rows.len() as u8];
+        for row in rows {
+            frame.extend(bincode::serialize(row).expect("row is serialized"));
+        }
+        frame
+    }
+
+    #[test]
+    fn rows_of_every_frame() {
+        let first = vec![vec![Type::VarChar("a".to_owned())], vec![Type::Bool(true)]];
+        let last = vec![vec![Type::Null]];
+        let mut response = frame(1, &first);
+        response.extend(frame(0, &last));
+        response.extend(vec![2]);
+        response.extend("done".as_bytes());
+        let mut buffer = Buffer::new(Cursor::new(response));
+
+        assert_eq!(buffer.server_code(), 3);
+        assert_eq!(
+            buffer.rows().expect("rows are read"),
+            vec![
+                vec![Type::VarChar("a".to_owned())],
+                vec![Type::Bool(true)],
+                vec![Type::Null]
+            ]
+        );
+        assert_eq!(buffer.server_code(), 2);
+        assert_eq!(buffer.content_as_string(), Ok("done".to_owned()));
+    }
+
+    #[test]
+    fn rows_of_empty_result() {
+        let mut buffer = Buffer::new(Cursor::new(frame(0, &[])));
+
+        assert_eq!(buffer.server_code(), 3);
+        assert_eq!(
+            buffer.rows().expect("rows are read"),
+            Vec::<Vec<Type>>::new()
+        );
+    }
 }
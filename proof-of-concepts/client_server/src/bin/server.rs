@@ -11,10 +11,12 @@ use std::io::{Read, Write};
 use mio::net::{TcpListener, TcpStream};
 use mio::{Events, Interest, Poll, Token};
 
-use sql_query_engine::{Engine, EngineEvent};
+use sql_query_engine::{Engine, EngineEvent, ErrorEvent, Row};
 
 const PORT: usize = 7000;
 const NETWORK_BUFFER_SIZE: usize = 256;
+// number of records sent in a single response frame, at most u8::MAX
+const RESULT_CHUNK_SIZE: usize = 128;
 const SERVER: Token = Token(0);
 
 fn main() -> io::Result<()> {
@@ -78,9 +80,17 @@ fn main() -> io::Result<()> {
                         Ok(len) => {
                             if let Some(stream) = sockets.get_mut(&token) {
                                 trace!("{} bytes read from network connection", len);
-                                let query = String::from_utf8(buffer[0..len].to_vec()).unwrap();
-                                debug!("Received query {}", query);
-                                let query_execution_result = engine.execute(query);
+                                let query_execution_result =
+                                    match String::from_utf8(buffer[0..len].to_vec()) {
+                                        Ok(query) => {
+                                            debug!("Received query {}", query);
+                                            engine.execute(query)
+                                        }
+                                        Err(error) => Err(ErrorEvent::EncodingError(format!(
+                                            "query is not a valid UTF-8 string: {}",
+                                            error.utf8_error()
+                                        ))),
+                                    };
                                 debug!("Query execution result");
                                 debug!("{:?}", query_execution_result);
                                 match query_execution_result {
@@ -92,20 +102,26 @@ fn main() -> io::Result<()> {
                                                     .as_bytes(),
                                             )?;
                                         }
-                                        EngineEvent::RecordInserted
-                                        | EngineEvent::RecordsUpdated
-                                        | EngineEvent::RecordsDeleted => {
+                                        EngineEvent::RecordsInserted(_)
+                                        | EngineEvent::RecordsUpdated(_)
+                                        | EngineEvent::RecordsDeleted(_)
+                                        | EngineEvent::IndexCreated(_)
+                                        | EngineEvent::TransactionStarted
+                                        | EngineEvent::TransactionCommitted
+                                        | EngineEvent::TransactionRolledBack => {
                                             stream.write_all(vec![2 as u8].as_slice())?;
                                             stream.write_all("done".as_bytes())?;
                                         }
                                         EngineEvent::RecordsSelected(records) => {
-                                            stream.write_all(vec![3 as u8].as_slice())?;
-                                            stream
-                                                .write_all(vec![records.len() as u8].as_slice())?;
-                                            for record in records {
-                                                let r = record.into_iter().collect::<Vec<u8>>();
-                                                stream.write_all(r.as_slice())?;
-                                            }
+                                            write_frames(
+                                                stream,
+                                                records.iter().map(Row::values),
+                                                RESULT_CHUNK_SIZE,
+                                            )?;
+                                        }
+                                        EngineEvent::QueryPlan(plan) => {
+                                            stream.write_all(vec![4 as u8].as_slice())?;
+                                            stream.write_all(plan.as_bytes())?;
                                         }
                                     },
                                     Err(e) => {
@@ -137,3 +153,48 @@ fn main() -> io::Result<()> {
         }
     }
 }
+
+/// writes selected rows as frames of `[3, continued, rows count, rows...]`,
+/// where `continued` is 1 for every frame but the last one;
+/// every row is serialized only when it is written
+fn write_frames<W: Write, R: serde::Serialize>(
+    stream: &mut W,
+    mut rows: impl ExactSizeIterator<Item = R>,
+    chunk_size: usize,
+) -> io::Result<()> {
+    loop {
+        let count = rows.len().min(chunk_size);
+        let continued = (rows.len() > count) as u8;
+        stream.write_all(vec![3 as u8, continued, count as u8].as_slice())?;
+        for row in rows.by_ref().take(count) {
+            bincode::serialize_into(&mut *stream, &row)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        }
+        if continued == 0 {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_exceeding_chunk_size() {
+        let mut frames = vec![];
+
+        write_frames(&mut frames, 1..=5u8, 2).expect("frames are written");
+
+        assert_eq!(frames, vec![3, 1, 2, 1, 2, 3, 1, 2, 3, 4, 3, 0, 1, 5]);
+    }
+
+    #[test]
+    fn empty_result() {
+        let mut frames = vec![];
+
+        write_frames(&mut frames, 0..0u8, 2).expect("frames are written");
+
+        assert_eq!(frames, vec![3, 0, 0]);
+    }
+}
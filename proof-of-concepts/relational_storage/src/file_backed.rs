@@ -0,0 +1,354 @@
+use super::{
+    Constraint, DurabilityLevel, Op, Predicate, SqlError, SqlResult, Statistics, Storage,
+    StorageType,
+};
+use crate::fsync::Fsync;
+use crate::in_memory::InMemoryStorage;
+use crate::types::Type;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// tables are kept in memory and the whole storage is written to the file after every write,
+/// or only once in a while with `Periodic` durability, writes of a transaction are written
+/// when the outermost transaction is committed
+pub struct FileBackedStorage {
+    path: PathBuf,
+    storage: InMemoryStorage,
+    fsync: Fsync,
+}
+
+impl FileBackedStorage {
+    pub fn open(path: &Path) -> io::Result<FileBackedStorage> {
+        let storage = if path.exists() {
+            bincode::deserialize(&fs::read(path)?)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+        } else {
+            InMemoryStorage::default()
+        };
+        Ok(FileBackedStorage {
+            path: path.to_owned(),
+            storage,
+            fsync: Fsync::new(),
+        })
+    }
+
+    /// replaces the file with a complete copy written aside,
+    /// so a crash while writing leaves the previous version rather than a partial one
+    fn save(&mut self) -> io::Result<()> {
+        let bytes = bincode::serialize(&self.storage)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        let written = self.path.with_extension("tmp");
+        let mut file = File::create(&written)?;
+        file.write_all(&bytes)?;
+        self.fsync.sync(&file)?;
+        fs::rename(written, &self.path)
+    }
+
+    fn persisted<T, E>(
+        &mut self,
+        result: Result<T, E>,
+        failure: impl Fn(io::Error) -> E,
+    ) -> Result<T, E> {
+        let value = result?;
+        if self.storage.open_transactions() == 0 && self.fsync.due() {
+            self.save().map_err(failure)?;
+        }
+        Ok(value)
+    }
+}
+
+// writes `Periodic` durability has not saved yet are saved when the storage is closed,
+// writes of transactions that are still open are dropped with it
+impl Drop for FileBackedStorage {
+    fn drop(&mut self) {
+        while self.storage.open_transactions() > 0 {
+            self.storage.rollback();
+        }
+        if self.fsync.pending() {
+            let _ = self.save();
+        }
+    }
+}
+
+impl Storage for FileBackedStorage {
+    fn create_table(
+        &mut self,
+        table_name: &String,
+        columns: Vec<(String, StorageType, HashSet<Constraint>)>,
+    ) -> Result<SqlResult, SqlError> {
+        let result = self.storage.create_table(table_name, columns);
+        self.persisted(result, |error| SqlError::Io(error.to_string()))
+    }
+
+    fn insert_into(
+        &mut self,
+        table_name: &String,
+        values: Vec<(String, Type)>,
+    ) -> Result<SqlResult, SqlError> {
+        let result = self.storage.insert_into(table_name, values);
+        self.persisted(result, |error| SqlError::Io(error.to_string()))
+    }
+
+    fn select(
+        &self,
+        table_name: &String,
+        predicate: Option<Predicate>,
+    ) -> Result<Vec<Vec<Type>>, SqlError> {
+        self.storage.select(table_name, predicate)
+    }
+
+    fn truncate(&mut self, table_name: &String) -> Result<SqlResult, SqlError> {
+        let result = self.storage.truncate(table_name);
+        self.persisted(result, |error| SqlError::Io(error.to_string()))
+    }
+
+    fn statistics(&self, table_name: &String) -> Result<Statistics, SqlError> {
+        self.storage.statistics(table_name)
+    }
+
+    fn set_durability_level(&mut self, level: DurabilityLevel) {
+        self.fsync.set_level(level);
+    }
+
+    fn apply_batch(&mut self, ops: Vec<Op>) -> Result<(), ()> {
+        let result = self.storage.apply_batch(ops);
+        self.persisted(result, |_| ())
+    }
+
+    fn replace_value(
+        &mut self,
+        table_name: &str,
+        key: &Type,
+        value: Vec<Type>,
+    ) -> Result<bool, ()> {
+        let result = self.storage.replace_value(table_name, key, value);
+        self.persisted(result, |_| ())
+    }
+
+    fn update(
+        &mut self,
+        table_name: &str,
+        assignment: (String, Type),
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError> {
+        let result = self.storage.update(table_name, assignment, predicate);
+        self.persisted(result, |error| SqlError::Io(error.to_string()))
+    }
+
+    fn delete(
+        &mut self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError> {
+        let result = self.storage.delete(table_name, predicate);
+        self.persisted(result, |error| SqlError::Io(error.to_string()))
+    }
+
+    fn create_index(&mut self, table_name: &str, column_name: &str) -> Result<SqlResult, SqlError> {
+        let result = self.storage.create_index(table_name, column_name);
+        self.persisted(result, |error| SqlError::Io(error.to_string()))
+    }
+
+    fn begin(&mut self) {
+        self.storage.begin();
+    }
+
+    // the file is replaced only when the whole transaction could be written,
+    // until then it holds the tables as they were before the transaction began
+    fn commit(&mut self) -> Result<(), SqlError> {
+        if self.storage.open_transactions() == 1 && self.fsync.due() {
+            self.save()
+                .map_err(|error| SqlError::Io(error.to_string()))?;
+        }
+        self.storage.commit()
+    }
+
+    // nothing was written to the file since the transaction began
+    fn rollback(&mut self) {
+        self.storage.rollback();
+    }
+}
+
+#[cfg(test)]
+mod persistence {
+    use super::*;
+    use num_bigint::BigInt;
+    use std::env;
+    use std::process;
+
+    fn table_name() -> String {
+        "table_name".to_owned()
+    }
+
+    fn int(value: i32) -> Type {
+        Type::Int(BigInt::from(value))
+    }
+
+    /// a file of the test in the temporary directory that is removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            let path = env::temp_dir().join(format!("{}-{}.db", name, process::id()));
+            let _ = fs::remove_file(&path);
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn rows_survive_reopening() {
+        let file = TempFile::new("rows_survive_reopening");
+        {
+            let mut storage = crate::file_backed(&file.0).expect("storage is opened");
+            assert_eq!(
+                storage.create_table(
+                    &table_name(),
+                    vec![
+                        ("key".to_owned(), StorageType::Integer, HashSet::new()),
+                        ("value".to_owned(), StorageType::Integer, HashSet::new()),
+                    ],
+                ),
+                Ok(SqlResult::TableCreated)
+            );
+            for (key, value) in &[(1, 10), (2, 20)] {
+                assert_eq!(
+                    storage.insert_into(
+                        &table_name(),
+                        vec![
+                            ("key".to_owned(), int(*key)),
+                            ("value".to_owned(), int(*value))
+                        ],
+                    ),
+                    Ok(SqlResult::RecordInserted)
+                );
+            }
+        }
+
+        let storage = crate::file_backed(&file.0).expect("storage is reopened");
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(20)]])
+        );
+    }
+
+    #[test]
+    fn writes_of_transaction_are_saved_on_commit() {
+        let file = TempFile::new("writes_of_transaction_are_saved_on_commit");
+        {
+            let mut storage = crate::file_backed(&file.0).expect("storage is opened");
+            assert_eq!(
+                storage.create_table(
+                    &table_name(),
+                    vec![("key".to_owned(), StorageType::Integer, HashSet::new())],
+                ),
+                Ok(SqlResult::TableCreated)
+            );
+            storage.begin();
+            assert_eq!(
+                storage.insert_into(&table_name(), vec![("key".to_owned(), int(1))]),
+                Ok(SqlResult::RecordInserted)
+            );
+            assert_eq!(storage.commit(), Ok(()));
+            storage.begin();
+            assert_eq!(
+                storage.insert_into(&table_name(), vec![("key".to_owned(), int(2))]),
+                Ok(SqlResult::RecordInserted)
+            );
+            // dropped before the second transaction is committed, as in a crash
+        }
+
+        let storage = crate::file_backed(&file.0).expect("storage is reopened");
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![vec![int(1)]]));
+    }
+
+    #[test]
+    fn periodic_durability_saves_once_in_a_while() {
+        let file = TempFile::new("periodic_durability_saves_once_in_a_while");
+        let mut storage = FileBackedStorage::open(&file.0).expect("storage is opened");
+        let syncs = storage.fsync.record();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![("key".to_owned(), StorageType::Integer, HashSet::new())],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        assert_eq!(
+            storage.insert_into(&table_name(), vec![("key".to_owned(), int(0))]),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(syncs.get(), 2);
+
+        storage.set_durability_level(DurabilityLevel::Periodic);
+        for key in 1..crate::fsync::PERIODIC_WRITES as i32 {
+            assert_eq!(
+                storage.insert_into(&table_name(), vec![("key".to_owned(), int(key))]),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        assert_eq!(syncs.get(), 2);
+        assert_eq!(
+            storage.insert_into(&table_name(), vec![("key".to_owned(), int(-1))]),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(syncs.get(), 3);
+    }
+
+    #[test]
+    fn periodic_writes_are_saved_when_dropped() {
+        let file = TempFile::new("periodic_writes_are_saved_when_dropped");
+        {
+            let mut storage = crate::file_backed(&file.0).expect("storage is opened");
+            storage.set_durability_level(DurabilityLevel::Periodic);
+            assert_eq!(
+                storage.create_table(
+                    &table_name(),
+                    vec![("key".to_owned(), StorageType::Integer, HashSet::new())],
+                ),
+                Ok(SqlResult::TableCreated)
+            );
+            assert_eq!(
+                storage.insert_into(&table_name(), vec![("key".to_owned(), int(1))]),
+                Ok(SqlResult::RecordInserted)
+            );
+            storage.begin();
+            assert_eq!(
+                storage.insert_into(&table_name(), vec![("key".to_owned(), int(2))]),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+
+        let storage = crate::file_backed(&file.0).expect("storage is reopened");
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![vec![int(1)]]));
+    }
+
+    #[test]
+    fn corrupt_file() {
+        let file = TempFile::new("corrupt_file");
+        {
+            let mut storage = crate::file_backed(&file.0).expect("storage is opened");
+            assert_eq!(
+                storage.create_table(
+                    &table_name(),
+                    vec![("key".to_owned(), StorageType::Integer, HashSet::new())],
+                ),
+                Ok(SqlResult::TableCreated)
+            );
+        }
+        let bytes = fs::read(&file.0).expect("file is written");
+        fs::write(&file.0, &bytes[..bytes.len() / 2]).expect("file is truncated");
+
+        match crate::file_backed(&file.0) {
+            Err(error) => assert_eq!(error.kind(), io::ErrorKind::InvalidData),
+            Ok(_) => panic!("partial file is read"),
+        }
+    }
+}
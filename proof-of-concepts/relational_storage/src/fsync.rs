@@ -0,0 +1,74 @@
+use super::DurabilityLevel;
+#[cfg(test)]
+use std::cell::Cell;
+use std::fs::File;
+use std::io;
+#[cfg(test)]
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// writes `Periodic` durability lets through before the next sync
+pub(crate) const PERIODIC_WRITES: usize = 64;
+/// time `Periodic` durability lets pass since the last sync before the next one
+const PERIODIC_INTERVAL: Duration = Duration::from_secs(1);
+
+// forces the written bytes of the file to disk, wrapped by tests to count the calls
+type Flush = Box<dyn FnMut(&File) -> io::Result<()>>;
+
+/// decides after which writes the written files are forced to disk
+pub(crate) struct Fsync {
+    level: DurabilityLevel,
+    unsynced: usize,
+    synced_at: Instant,
+    sync: Flush,
+}
+
+impl Fsync {
+    pub(crate) fn new() -> Fsync {
+        Fsync {
+            level: DurabilityLevel::Synchronous,
+            unsynced: 0,
+            synced_at: Instant::now(),
+            sync: Box::new(|file| file.sync_data()),
+        }
+    }
+
+    pub(crate) fn set_level(&mut self, level: DurabilityLevel) {
+        self.level = level;
+    }
+
+    /// counts a write, returns whether the written data has to be synced now
+    pub(crate) fn due(&mut self) -> bool {
+        self.unsynced += 1;
+        match self.level {
+            DurabilityLevel::Synchronous => true,
+            DurabilityLevel::Periodic => {
+                self.unsynced >= PERIODIC_WRITES || self.synced_at.elapsed() >= PERIODIC_INTERVAL
+            }
+        }
+    }
+
+    /// whether there are writes that were not synced yet
+    pub(crate) fn pending(&self) -> bool {
+        self.unsynced > 0
+    }
+
+    pub(crate) fn sync(&mut self, file: &File) -> io::Result<()> {
+        (self.sync)(file)?;
+        self.unsynced = 0;
+        self.synced_at = Instant::now();
+        Ok(())
+    }
+
+    /// counts the syncs from now on in the returned cell
+    #[cfg(test)]
+    pub(crate) fn record(&mut self) -> Rc<Cell<usize>> {
+        let syncs = Rc::new(Cell::new(0));
+        let counted = syncs.clone();
+        self.sync = Box::new(move |file| {
+            counted.set(counted.get() + 1);
+            file.sync_data()
+        });
+        syncs
+    }
+}
@@ -1,14 +1,31 @@
-use super::{Constraint, Predicate, SqlError, SqlResult, Storage, StorageType};
+use super::{
+    ColumnStatistics, Constraint, DurabilityLevel, Op, Predicate, SqlError, SqlResult, Statistics,
+    Storage, StorageType,
+};
 use crate::types::Type;
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::ops::Deref;
+use std::ops::{Bound, Deref, RangeBounds};
 
-#[derive(Default)]
+/// keys of the rows by the value of the indexed column, in key order
+type Index = HashMap<Type, Vec<Type>>;
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct InMemoryStorage {
     next_id: u32,
     tables: HashMap<String, u32>,
     metadata: HashMap<u32, TableDefinition>,
     data: HashMap<u32, BTreeMap<Type, Vec<Type>>>,
+    sequences: HashMap<u32, BigInt>,
+    // number of occurrences of every value per column
+    histograms: HashMap<u32, Vec<BTreeMap<Type, usize>>>,
+    // indexes of a table by the position of their column
+    indexes: HashMap<u32, HashMap<usize, Index>>,
+    // the storage as it was when every open transaction began, the innermost one last
+    #[serde(skip)]
+    transactions: Vec<InMemoryStorage>,
 }
 
 impl Storage for InMemoryStorage {
@@ -20,16 +37,18 @@ impl Storage for InMemoryStorage {
         if self.tables.contains_key(table_name) {
             Err(SqlError::TableAlreadyExists)
         } else {
-            let mut table_definition = TableDefinition {
-                columns: HashMap::new(),
-            };
+            let mut table_definition = TableDefinition { columns: vec![] };
             let column_names = (&columns)
                 .into_iter()
                 .map(|(name, _, _)| name)
                 .cloned()
                 .collect::<Vec<String>>();
             for (column_name, storage_type, constraints) in columns {
-                if table_definition.columns.contains_key(&column_name) {
+                if table_definition
+                    .columns
+                    .iter()
+                    .any(|(name, _)| name == &column_name)
+                {
                     return Err(SqlError::DuplicateColumnsName);
                 }
                 for constraint in &constraints {
@@ -44,19 +63,22 @@ impl Storage for InMemoryStorage {
                         }
                     }
                 }
-                table_definition.columns.insert(
+                table_definition.columns.push((
                     column_name,
                     ColumnDefinition {
                         sql_type: storage_type,
                         constraints,
                     },
-                );
+                ));
             }
             let id = self.next_id;
             self.next_id += 1;
+            self.histograms
+                .insert(id, vec![BTreeMap::new(); table_definition.columns.len()]);
             self.tables.insert(table_name.clone(), id);
             self.metadata.insert(id, table_definition);
             self.data.insert(id, BTreeMap::new());
+            self.sequences.insert(id, BigInt::zero());
             Ok(SqlResult::TableCreated)
         }
     }
@@ -66,25 +88,300 @@ impl Storage for InMemoryStorage {
         table_name: &String,
         values: Vec<(String, Type)>,
     ) -> Result<SqlResult, SqlError> {
-        if !self.tables.contains_key(table_name) {
-            Err(SqlError::TableDoesNotExists)
-        } else {
-            self.read_write(table_name).map(|data| {
-                for (_, value) in values.into_iter() {
-                    data.insert(value.clone(), vec![value]);
+        let id = match self.tables.get(table_name) {
+            Some(id) => *id,
+            None => return Err(SqlError::TableDoesNotExists),
+        };
+        let mut row = vec![];
+        // kept only when the row is written, an explicit value of a serial column moves it
+        // past that value so that the next generated one does not take the same key
+        let mut sequence = self.sequences[&id].clone();
+        for (column_name, column_definition) in &self.metadata[&id].columns {
+            match values.iter().find(|(name, _)| name == column_name) {
+                Some((_, value)) => {
+                    if let Type::Int(value) = value {
+                        if column_definition.sql_type.is_serial() && *value > sequence {
+                            sequence = value.clone();
+                        }
+                    }
+                    row.push(value.clone())
                 }
-            });
-            Ok(SqlResult::RecordInserted)
+                None if column_definition.sql_type.is_serial() => {
+                    sequence += BigInt::one();
+                    row.push(Type::Int(sequence.clone()));
+                }
+                None => return Err(SqlError::MissingColumnValue(column_name.clone())),
+            }
         }
+        if self.data[&id].contains_key(&row[0]) {
+            return Err(SqlError::DuplicateKey(row[0].clone()));
+        }
+        self.sequences.insert(id, sequence);
+        self.track(id, &row);
+        self.data.get_mut(&id).unwrap().insert(row[0].clone(), row);
+        Ok(SqlResult::RecordInserted)
     }
 
     fn select(
+        &self,
+        table_name: &String,
+        predicate: Option<Predicate>,
+    ) -> Result<Vec<Vec<Type>>, SqlError> {
+        match predicate {
+            Some(Predicate::And(left, right)) => {
+                let right = self
+                    .select(table_name, Some(*right))?
+                    .into_iter()
+                    .map(|row| row[0].clone())
+                    .collect::<HashSet<Type>>();
+                self.select(table_name, Some(*left)).map(|rows| {
+                    rows.into_iter()
+                        .filter(|row| right.contains(&row[0]))
+                        .collect()
+                })
+            }
+            Some(Predicate::ColumnEqual(column_name, value)) => {
+                self.select_by_column(table_name, &column_name, &value)
+            }
+            Some(Predicate::Match(column_name, terms)) => {
+                self.scan(table_name, &column_name, |value| {
+                    value.contains_terms(&terms) == Ok(true)
+                })
+            }
+            Some(Predicate::Like(column_name, pattern)) => {
+                self.scan(table_name, &column_name, |value| {
+                    value.like(&pattern) == Ok(true)
+                })
+            }
+            Some(Predicate::Not(predicate)) if !on_key(&predicate) => {
+                if unknown(&predicate) {
+                    return Ok(vec![]);
+                }
+                let excluded = self
+                    .select(table_name, Some(*predicate))?
+                    .into_iter()
+                    .map(|row| row[0].clone())
+                    .collect::<HashSet<Type>>();
+                Ok(self
+                    .select(table_name, None)?
+                    .into_iter()
+                    .filter(|row| !excluded.contains(&row[0]))
+                    .collect())
+            }
+            Some(Predicate::Or(left, right)) => {
+                let mut rows = BTreeMap::new();
+                for row in self
+                    .select(table_name, Some(*left))?
+                    .into_iter()
+                    .chain(self.select(table_name, Some(*right))?)
+                {
+                    rows.insert(row[0].clone(), row);
+                }
+                Ok(rows.into_values().collect())
+            }
+            predicate => self.select_by_key(table_name, predicate),
+        }
+    }
+
+    fn truncate(&mut self, table_name: &String) -> Result<SqlResult, SqlError> {
+        match self.tables.get(table_name) {
+            Some(id) => {
+                self.data.get_mut(id).unwrap().clear();
+                self.sequences.insert(*id, BigInt::zero());
+                for histogram in self.histograms.get_mut(id).unwrap() {
+                    histogram.clear();
+                }
+                for index in self.indexes.entry(*id).or_default().values_mut() {
+                    index.clear();
+                }
+                Ok(SqlResult::TableTruncated)
+            }
+            None => Err(SqlError::TableDoesNotExists),
+        }
+    }
+
+    fn statistics(&self, table_name: &String) -> Result<Statistics, SqlError> {
+        match self.tables.get(table_name) {
+            Some(id) => Ok(Statistics {
+                row_count: self.data[id].len(),
+                columns: self.metadata[id]
+                    .columns
+                    .iter()
+                    .zip(self.histograms[id].iter())
+                    .map(|((name, _), histogram)| ColumnStatistics {
+                        name: name.clone(),
+                        min: histogram.keys().next().cloned(),
+                        max: histogram.keys().next_back().cloned(),
+                        distinct_values: histogram.len(),
+                    })
+                    .collect(),
+            }),
+            None => Err(SqlError::TableDoesNotExists),
+        }
+    }
+
+    fn set_durability_level(&mut self, _level: DurabilityLevel) {}
+
+    fn replace_value(
+        &mut self,
+        table_name: &str,
+        key: &Type,
+        value: Vec<Type>,
+    ) -> Result<bool, ()> {
+        let id = *self.tables.get(table_name).ok_or(())?;
+        if value.first() != Some(key) {
+            return Err(());
+        }
+        match self.data.get_mut(&id).unwrap().get_mut(key) {
+            Some(row) => {
+                let replaced = std::mem::replace(row, value.clone());
+                self.untrack(id, &replaced);
+                self.track(id, &value);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn update(
+        &mut self,
+        table_name: &str,
+        assignment: (String, Type),
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError> {
+        let table_name = table_name.to_owned();
+        let id = *self
+            .tables
+            .get(&table_name)
+            .ok_or(SqlError::TableDoesNotExists)?;
+        self.column(id, &assignment.0)?;
+        let rows = self.select(&table_name, predicate)?;
+        // a row that can't be written, e.g. as it would take the key of another one,
+        // puts back the rows written before it
+        let data = self.data[&id].clone();
+        let histograms = self.histograms[&id].clone();
+        let indexes = self.indexes.get(&id).cloned();
+        for row in &rows {
+            if let Err(error) = self.update_row(id, row.clone(), vec![assignment.clone()]) {
+                self.data.insert(id, data);
+                self.histograms.insert(id, histograms);
+                match indexes {
+                    Some(indexes) => self.indexes.insert(id, indexes),
+                    None => self.indexes.remove(&id),
+                };
+                return Err(error);
+            }
+        }
+        Ok(rows.len())
+    }
+
+    fn delete(
         &mut self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError> {
+        let table_name = table_name.to_owned();
+        let rows = self.select(&table_name, predicate)?;
+        let id = self.tables[&table_name];
+        for row in &rows {
+            self.delete_row(id, &row[0]);
+        }
+        Ok(rows.len())
+    }
+
+    // the storage is borrowed mutably for the whole batch, so no other write can interleave
+    fn apply_batch(&mut self, ops: Vec<Op>) -> Result<(), ()> {
+        let data = self.data.clone();
+        let histograms = self.histograms.clone();
+        let sequences = self.sequences.clone();
+        let indexes = self.indexes.clone();
+        for op in ops {
+            let applied = match op {
+                Op::Insert { table_name, values } => self
+                    .insert_into(&table_name, values)
+                    .map(|_| ())
+                    .map_err(|_| ()),
+                Op::Update {
+                    table_name,
+                    key,
+                    values,
+                } => match self.tables.get(&table_name).copied() {
+                    Some(id) => match self.data[&id].get(&key).cloned() {
+                        Some(row) => self.update_row(id, row, values).map_err(|_| ()),
+                        None => Err(()),
+                    },
+                    None => Err(()),
+                },
+                Op::Delete { table_name, key } => match self.tables.get(&table_name).copied() {
+                    Some(id) => self.delete_row(id, &key).map(|_| ()).ok_or(()),
+                    None => Err(()),
+                },
+            };
+            if applied.is_err() {
+                self.data = data;
+                self.histograms = histograms;
+                self.sequences = sequences;
+                self.indexes = indexes;
+                return Err(());
+            }
+        }
+        Ok(())
+    }
+
+    fn create_index(&mut self, table_name: &str, column_name: &str) -> Result<SqlResult, SqlError> {
+        let id = *self
+            .tables
+            .get(table_name)
+            .ok_or(SqlError::TableDoesNotExists)?;
+        let column = self.column(id, column_name)?;
+        let mut index = Index::new();
+        for row in self.data[&id].values() {
+            index
+                .entry(row[column].clone())
+                .or_default()
+                .push(row[0].clone());
+        }
+        self.indexes.entry(id).or_default().insert(column, index);
+        Ok(SqlResult::IndexCreated)
+    }
+
+    fn begin(&mut self) {
+        // the snapshot is taken without the transactions that are open already
+        let transactions = std::mem::take(&mut self.transactions);
+        let snapshot = self.clone();
+        self.transactions = transactions;
+        self.transactions.push(snapshot);
+    }
+
+    fn commit(&mut self) -> Result<(), SqlError> {
+        self.transactions.pop();
+        Ok(())
+    }
+
+    fn rollback(&mut self) {
+        if let Some(snapshot) = self.transactions.pop() {
+            let transactions = std::mem::take(&mut self.transactions);
+            *self = snapshot;
+            self.transactions = transactions;
+        }
+    }
+}
+
+impl InMemoryStorage {
+    /// how many transactions are open, nested ones included
+    pub(crate) fn open_transactions(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// rows of a predicate answered from the ordered keys alone
+    fn select_by_key(
+        &self,
         table_name: &String,
         predicate: Option<Predicate>,
-    ) -> Result<Vec<Vec<Type>>, ()> {
+    ) -> Result<Vec<Vec<Type>>, SqlError> {
         self.read_only(table_name)
             .map(|data| match predicate {
+                Some(predicate) if unknown(&predicate) => vec![],
                 Some(Predicate::Equal(value)) => {
                     data.get(&value).cloned().map(|v| vec![v]).unwrap_or(vec![])
                 }
@@ -93,11 +390,37 @@ impl Storage for InMemoryStorage {
                     .map(|(_key, value)| value)
                     .cloned()
                     .collect(),
+                Some(Predicate::Range {
+                    lo,
+                    hi,
+                    lo_inclusive,
+                    hi_inclusive,
+                }) => match bounds(&lo, &hi, lo_inclusive, hi_inclusive) {
+                    Some(bounds) => data
+                        .range::<Type, _>(bounds)
+                        .map(|(_key, value)| value)
+                        .cloned()
+                        .collect(),
+                    None => vec![],
+                },
                 Some(Predicate::In(values)) => data
                     .values()
                     .filter(|value| values.contains(&value[0]))
                     .cloned()
                     .collect(),
+                Some(comparison @ Predicate::LessThan(_))
+                | Some(comparison @ Predicate::LessThanOrEqual(_))
+                | Some(comparison @ Predicate::GreaterThan(_))
+                | Some(comparison @ Predicate::GreaterThanOrEqual(_)) => {
+                    match comparison_bounds(&comparison) {
+                        Some(bounds) => data
+                            .range::<Type, _>(bounds)
+                            .map(|(_key, value)| value)
+                            .cloned()
+                            .collect(),
+                        None => vec![],
+                    }
+                }
                 Some(Predicate::Not(predicate)) => {
                     if let Predicate::Between(low, high) = predicate.deref() {
                         data.range(..low)
@@ -105,22 +428,108 @@ impl Storage for InMemoryStorage {
                             .map(|(_key, value)| value)
                             .cloned()
                             .collect()
+                    } else if let Predicate::Range {
+                        lo,
+                        hi,
+                        lo_inclusive,
+                        hi_inclusive,
+                    } = predicate.deref()
+                    {
+                        match bounds(lo, hi, *lo_inclusive, *hi_inclusive) {
+                            Some(bounds) => data
+                                .iter()
+                                .filter(|(key, _value)| !bounds.contains(*key))
+                                .map(|(_key, value)| value)
+                                .cloned()
+                                .collect(),
+                            None => data.values().cloned().collect(),
+                        }
+                    } else if let Some(bounds) = comparison_bounds(predicate.deref()) {
+                        data.iter()
+                            .filter(|(key, _value)| !bounds.contains(*key))
+                            .map(|(_key, value)| value)
+                            .cloned()
+                            .collect()
+                    } else if let Predicate::Equal(value) = predicate.deref() {
+                        data.iter()
+                            .filter(|(key, _value)| *key != value)
+                            .map(|(_key, value)| value)
+                            .cloned()
+                            .collect()
                     } else if let Predicate::In(values) = predicate.deref() {
                         data.values()
                             .filter(|value| !values.contains(&value[0]))
                             .cloned()
                             .collect()
                     } else {
-                        vec![]
+                        unreachable!("other predicates are negated by their complement")
                     }
                 }
+                Some(Predicate::And(_, _)) | Some(Predicate::Or(_, _)) => {
+                    unreachable!("combinations are selected by their parts")
+                }
+                Some(Predicate::ColumnEqual(_, _))
+                | Some(Predicate::Match(_, _))
+                | Some(Predicate::Like(_, _)) => {
+                    unreachable!("column values are selected by the column")
+                }
                 None => data.values().cloned().collect(),
             })
-            .ok_or_else(|| ())
+            .ok_or(SqlError::TableDoesNotExists)
+    }
+
+    /// rows whose value in the column passes the test, found by a scan
+    fn scan(
+        &self,
+        table_name: &String,
+        column_name: &str,
+        test: impl Fn(&Type) -> bool,
+    ) -> Result<Vec<Vec<Type>>, SqlError> {
+        let id = *self
+            .tables
+            .get(table_name)
+            .ok_or(SqlError::TableDoesNotExists)?;
+        let column = self.column(id, column_name)?;
+        Ok(self.data[&id]
+            .values()
+            .filter(|row| test(&row[column]))
+            .cloned()
+            .collect())
+    }
+
+    /// rows holding the value in the column, from its index when there is one
+    fn select_by_column(
+        &self,
+        table_name: &String,
+        column_name: &str,
+        value: &Type,
+    ) -> Result<Vec<Vec<Type>>, SqlError> {
+        let id = *self
+            .tables
+            .get(table_name)
+            .ok_or(SqlError::TableDoesNotExists)?;
+        let column = self.column(id, column_name)?;
+        let data = &self.data[&id];
+        if *value == Type::Null {
+            return Ok(vec![]);
+        }
+        match self
+            .indexes
+            .get(&id)
+            .and_then(|indexes| indexes.get(&column))
+        {
+            Some(index) => Ok(index
+                .get(value)
+                .map(|keys| keys.iter().map(|key| data[key].clone()).collect())
+                .unwrap_or_default()),
+            None => Ok(data
+                .values()
+                .filter(|row| row[column] == *value)
+                .cloned()
+                .collect()),
+        }
     }
-}
 
-impl InMemoryStorage {
     fn read_only(&self, table_name: &String) -> Option<&BTreeMap<Type, Vec<Type>>> {
         match self.tables.get(table_name) {
             Some(id) => self.data.get(id),
@@ -128,6 +537,75 @@ impl InMemoryStorage {
         }
     }
 
+    fn track(&mut self, id: u32, row: &[Type]) {
+        for (histogram, value) in self.histograms.get_mut(&id).unwrap().iter_mut().zip(row) {
+            *histogram.entry(value.clone()).or_insert(0) += 1;
+        }
+        for (column, index) in self.indexes.entry(id).or_default() {
+            let keys = index.entry(row[*column].clone()).or_default();
+            if let Err(position) = keys.binary_search(&row[0]) {
+                keys.insert(position, row[0].clone());
+            }
+        }
+    }
+
+    fn untrack(&mut self, id: u32, row: &[Type]) {
+        for (histogram, value) in self.histograms.get_mut(&id).unwrap().iter_mut().zip(row) {
+            if let Some(count) = histogram.get_mut(value) {
+                *count -= 1;
+                if *count == 0 {
+                    histogram.remove(value);
+                }
+            }
+        }
+        for (column, index) in self.indexes.entry(id).or_default() {
+            if let Some(keys) = index.get_mut(&row[*column]) {
+                keys.retain(|key| *key != row[0]);
+                if keys.is_empty() {
+                    index.remove(&row[*column]);
+                }
+            }
+        }
+    }
+
+    /// position of the column in the rows of the table
+    fn column(&self, id: u32, column_name: &str) -> Result<usize, SqlError> {
+        self.metadata[&id]
+            .columns
+            .iter()
+            .position(|(name, _)| name == column_name)
+            .ok_or_else(|| SqlError::NotExistentColumn(column_name.to_owned()))
+    }
+
+    /// writes the values into the row, nothing is written unless every column exists
+    /// and the key of the row is not taken by another one
+    fn update_row(
+        &mut self,
+        id: u32,
+        mut row: Vec<Type>,
+        values: Vec<(String, Type)>,
+    ) -> Result<(), SqlError> {
+        let key = row[0].clone();
+        for (column_name, value) in values {
+            let index = self.column(id, &column_name)?;
+            row[index] = value;
+        }
+        // a row moved onto the key of another row would silently take its place
+        if row[0] != key && self.data[&id].contains_key(&row[0]) {
+            return Err(SqlError::DuplicateKey(row[0].clone()));
+        }
+        self.delete_row(id, &key);
+        self.track(id, &row);
+        self.data.get_mut(&id).unwrap().insert(row[0].clone(), row);
+        Ok(())
+    }
+
+    fn delete_row(&mut self, id: u32, key: &Type) -> Option<Vec<Type>> {
+        let row = self.data.get_mut(&id).unwrap().remove(key)?;
+        self.untrack(id, &row);
+        Some(row)
+    }
+
     fn read_write(&mut self, table_name: &String) -> Option<&mut BTreeMap<Type, Vec<Type>>> {
         match self.tables.get(table_name) {
             Some(id) => self.data.get_mut(id),
@@ -136,15 +614,140 @@ impl InMemoryStorage {
     }
 }
 
+/// bounds of a range scan, `None` when no key can be in the range
+fn bounds<'a>(
+    lo: &'a Option<Type>,
+    hi: &'a Option<Type>,
+    lo_inclusive: bool,
+    hi_inclusive: bool,
+) -> Option<(Bound<&'a Type>, Bound<&'a Type>)> {
+    if let (Some(lo), Some(hi)) = (lo, hi) {
+        if lo > hi || (lo == hi && !(lo_inclusive && hi_inclusive)) {
+            return None;
+        }
+    }
+    let bound = |value: &'a Option<Type>, inclusive: bool| match value {
+        Some(value) if inclusive => Bound::Included(value),
+        Some(value) => Bound::Excluded(value),
+        None => Bound::Unbounded,
+    };
+    Some((bound(lo, lo_inclusive), bound(hi, hi_inclusive)))
+}
+
+/// whether the predicate compares keys with NULL, which no key satisfies
+/// and neither does its negation
+fn unknown(predicate: &Predicate) -> bool {
+    match predicate {
+        Predicate::Equal(value)
+        | Predicate::LessThan(value)
+        | Predicate::LessThanOrEqual(value)
+        | Predicate::GreaterThan(value)
+        | Predicate::GreaterThanOrEqual(value) => *value == Type::Null,
+        Predicate::Between(low, high) => *low == Type::Null || *high == Type::Null,
+        Predicate::Range { lo, hi, .. } => *lo == Some(Type::Null) || *hi == Some(Type::Null),
+        Predicate::ColumnEqual(_, value) => *value == Type::Null,
+        Predicate::Not(predicate) => unknown(predicate),
+        _ => false,
+    }
+}
+
+/// whether the predicate is answered from the keys, so is its negation
+fn on_key(predicate: &Predicate) -> bool {
+    matches!(
+        predicate,
+        Predicate::Equal(_)
+            | Predicate::Between(_, _)
+            | Predicate::Range { .. }
+            | Predicate::In(_)
+            | Predicate::LessThan(_)
+            | Predicate::LessThanOrEqual(_)
+            | Predicate::GreaterThan(_)
+            | Predicate::GreaterThanOrEqual(_)
+    )
+}
+
+/// bounds of keys compared with a value, `None` for other predicates
+fn comparison_bounds(predicate: &Predicate) -> Option<(Bound<&Type>, Bound<&Type>)> {
+    match predicate {
+        Predicate::LessThan(value) => Some((Bound::Unbounded, Bound::Excluded(value))),
+        Predicate::LessThanOrEqual(value) => Some((Bound::Unbounded, Bound::Included(value))),
+        Predicate::GreaterThan(value) => Some((Bound::Excluded(value), Bound::Unbounded)),
+        Predicate::GreaterThanOrEqual(value) => Some((Bound::Included(value), Bound::Unbounded)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct TableDefinition {
-    columns: HashMap<String, ColumnDefinition>,
+    columns: Vec<(String, ColumnDefinition)>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 struct ColumnDefinition {
     sql_type: StorageType,
     constraints: HashSet<Constraint>,
 }
 
+#[cfg(test)]
+mod test_helpers {
+    use super::*;
+
+    use num_bigint::BigInt;
+
+    pub(crate) fn table_name() -> String {
+        "table_name".to_owned()
+    }
+
+    pub(crate) fn int(value: i32) -> Type {
+        Type::Int(BigInt::from(value))
+    }
+
+    pub(crate) fn storage_with_rows(rows: &[(i32, i32)]) -> InMemoryStorage {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), StorageType::Integer, HashSet::new()),
+                    ("value".to_owned(), StorageType::Integer, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        for (id, value) in rows {
+            assert_eq!(
+                storage.insert_into(
+                    &table_name(),
+                    vec![
+                        ("id".to_owned(), int(*id)),
+                        ("value".to_owned(), int(*value))
+                    ]
+                ),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        storage
+    }
+
+    pub(crate) fn storage_with_keys(keys: &[i32]) -> InMemoryStorage {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![("key".to_owned(), StorageType::Integer, HashSet::new())],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        for key in keys {
+            assert_eq!(
+                storage.insert_into(&table_name(), vec![("key".to_owned(), int(*key))]),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        storage
+    }
+}
+
 #[cfg(test)]
 mod table_creation {
     use super::*;
@@ -451,53 +1054,1222 @@ mod selections {
 }
 
 #[cfg(test)]
-mod insertions {
+mod text_search {
     use super::*;
 
-    use num_bigint::BigInt;
-    use num_traits::Zero;
+    fn text(value: &str) -> Type {
+        Type::VarChar(value.to_owned())
+    }
 
-    use super::super::*;
+    #[test]
+    fn select_rows_matching_all_terms() {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &"table_name".to_owned(),
+                vec![("name".to_owned(), StorageType::VarChar, HashSet::new())],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        for name in &["Foo Bar", "foo", "bar baz foo"] {
+            assert_eq!(
+                storage.insert_into(
+                    &"table_name".to_owned(),
+                    vec![("name".to_owned(), text(name))]
+                ),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
 
-    fn zero() -> Type {
-        Type::Int(BigInt::zero())
+        assert_eq!(
+            storage.select(
+                &"table_name".to_owned(),
+                Some(Predicate::Match(
+                    "name".to_owned(),
+                    vec!["foo".to_owned(), "bar".to_owned()]
+                )),
+            ),
+            Ok(vec![vec![text("Foo Bar")], vec![text("bar baz foo")]])
+        );
     }
 
     #[test]
-    fn insert_row_into_nonexisting_table() {
+    fn select_rows_matching_terms_in_other_column() {
         let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &"table_name".to_owned(),
+                vec![
+                    ("id".to_owned(), StorageType::Serial, HashSet::new()),
+                    ("name".to_owned(), StorageType::VarChar, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        for name in &["hello world", "goodbye"] {
+            assert_eq!(
+                storage.insert_into(
+                    &"table_name".to_owned(),
+                    vec![("name".to_owned(), text(name))]
+                ),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        let hello = || Predicate::Match("name".to_owned(), vec!["hello".to_owned()]);
+        let id = |value: i32| Type::Int(value.into());
 
         assert_eq!(
-            storage.insert_into(
+            storage.select(&"table_name".to_owned(), Some(hello())),
+            Ok(vec![vec![id(1), text("hello world")]])
+        );
+        assert_eq!(
+            storage.select(
                 &"table_name".to_owned(),
-                vec![("column_name".to_owned(), zero())],
+                Some(Predicate::Not(Box::new(hello())))
             ),
-            Err(SqlError::TableDoesNotExists)
-        )
+            Ok(vec![vec![id(2), text("goodbye")]])
+        );
+        assert_eq!(
+            storage.select(
+                &"table_name".to_owned(),
+                Some(Predicate::Match("nope".to_owned(), vec![]))
+            ),
+            Err(SqlError::NotExistentColumn("nope".to_owned()))
+        );
     }
 
     #[test]
-    fn insert_row_into_table() {
+    fn select_rows_like_pattern() {
         let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &"table_name".to_owned(),
+                vec![("name".to_owned(), StorageType::VarChar, HashSet::new())],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        for name in &["alice", "bob", "alina", "carla"] {
+            assert_eq!(
+                storage.insert_into(
+                    &"table_name".to_owned(),
+                    vec![("name".to_owned(), text(name))]
+                ),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+
+        assert_eq!(
+            storage.select(
+                &"table_name".to_owned(),
+                Some(Predicate::Like("name".to_owned(), "al%".to_owned()))
+            ),
+            Ok(vec![vec![text("alice")], vec![text("alina")]])
+        );
+        assert_eq!(
+            storage.select(
+                &"table_name".to_owned(),
+                Some(Predicate::Like("name".to_owned(), "%la".to_owned()))
+            ),
+            Ok(vec![vec![text("carla")]])
+        );
+        assert_eq!(
+            storage.select(
+                &"table_name".to_owned(),
+                Some(Predicate::Like("name".to_owned(), "_ob".to_owned()))
+            ),
+            Ok(vec![vec![text("bob")]])
+        );
+    }
 
+    #[test]
+    fn select_rows_like_pattern_in_other_column() {
+        let mut storage = InMemoryStorage::default();
         assert_eq!(
             storage.create_table(
                 &"table_name".to_owned(),
-                vec![(
-                    "column_name".to_owned(),
-                    StorageType::Integer,
-                    HashSet::new()
-                )],
+                vec![
+                    ("id".to_owned(), StorageType::Serial, HashSet::new()),
+                    ("name".to_owned(), StorageType::VarChar, HashSet::new())
+                ],
             ),
             Ok(SqlResult::TableCreated)
         );
+        for name in &["serial one", "other"] {
+            assert_eq!(
+                storage.insert_into(
+                    &"table_name".to_owned(),
+                    vec![("name".to_owned(), text(name))]
+                ),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        let id = |value: i32| Type::Int(value.into());
 
         assert_eq!(
-            storage.insert_into(
+            storage.select(
                 &"table_name".to_owned(),
-                vec![("column_name".to_owned(), zero())],
+                Some(Predicate::Like("name".to_owned(), "serial%".to_owned()))
+            ),
+            Ok(vec![vec![id(1), text("serial one")]])
+        );
+        assert_eq!(
+            storage.select(
+                &"table_name".to_owned(),
+                Some(Predicate::Not(Box::new(Predicate::Like(
+                    "name".to_owned(),
+                    "serial%".to_owned()
+                ))))
+            ),
+            Ok(vec![vec![id(2), text("other")]])
+        );
+    }
+}
+
+#[cfg(test)]
+mod insertions {
+    use super::*;
+
+    use num_bigint::BigInt;
+    use num_traits::Zero;
+
+    use super::super::*;
+    use super::test_helpers::*;
+
+    fn zero() -> Type {
+        Type::Int(BigInt::zero())
+    }
+
+    #[test]
+    fn insert_row_into_nonexisting_table() {
+        let mut storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.insert_into(
+                &"table_name".to_owned(),
+                vec![("column_name".to_owned(), zero())],
+            ),
+            Err(SqlError::TableDoesNotExists)
+        )
+    }
+
+    #[test]
+    fn insert_row_into_table() {
+        let mut storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.create_table(
+                &"table_name".to_owned(),
+                vec![(
+                    "column_name".to_owned(),
+                    StorageType::Integer,
+                    HashSet::new()
+                )],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+
+        assert_eq!(
+            storage.insert_into(
+                &"table_name".to_owned(),
+                vec![("column_name".to_owned(), zero())],
+            ),
+            Ok(SqlResult::RecordInserted)
+        )
+    }
+
+    #[test]
+    fn insert_row_with_existing_key() {
+        let mut storage = storage_with_rows(&[(1, 10)]);
+
+        assert_eq!(
+            storage.insert_into(
+                &table_name(),
+                vec![("id".to_owned(), int(1)), ("value".to_owned(), int(20))],
+            ),
+            Err(SqlError::DuplicateKey(int(1)))
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)]])
+        );
+    }
+
+    #[test]
+    fn explicit_serial_value_moves_sequence() {
+        let mut storage = InMemoryStorage::default();
+        let text = |value: &str| Type::VarChar(value.to_owned());
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), StorageType::Serial, HashSet::new()),
+                    ("name".to_owned(), StorageType::VarChar, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+
+        assert_eq!(
+            storage.insert_into(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), int(1)),
+                    ("name".to_owned(), text("explicit"))
+                ],
+            ),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(
+            storage.insert_into(&table_name(), vec![("name".to_owned(), text("generated"))]),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![
+                vec![int(1), text("explicit")],
+                vec![int(2), text("generated")]
+            ])
+        );
+    }
+}
+
+#[cfg(test)]
+mod secondary_index {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    fn value_equal(value: i32) -> Option<Predicate> {
+        Some(Predicate::ColumnEqual("value".to_owned(), int(value)))
+    }
+
+    #[test]
+    fn same_rows_with_and_without_index() {
+        let mut storage =
+            storage_with_rows(&(1..=1000).map(|key| (key, key % 7)).collect::<Vec<_>>());
+        let scanned = storage.select(&table_name(), value_equal(3));
+
+        assert_eq!(
+            storage.create_index(&table_name(), "value"),
+            Ok(SqlResult::IndexCreated)
+        );
+        assert_eq!(
+            storage.insert_into(
+                &table_name(),
+                vec![("id".to_owned(), int(1001)), ("value".to_owned(), int(3))]
+            ),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(
+            storage.delete(&table_name(), Some(Predicate::Equal(int(1001)))),
+            Ok(1)
+        );
+
+        let indexed = storage.select(&table_name(), value_equal(3));
+        assert_eq!(indexed.as_ref().map(Vec::len), Ok(143));
+        assert_eq!(indexed, scanned);
+    }
+
+    #[test]
+    fn update_moves_key_between_entries() {
+        let mut storage = storage_with_rows(&[(1, 10), (2, 10)]);
+        assert_eq!(
+            storage.create_index(&table_name(), "value"),
+            Ok(SqlResult::IndexCreated)
+        );
+
+        assert_eq!(
+            storage.update(
+                &table_name(),
+                ("value".to_owned(), int(20)),
+                Some(Predicate::Equal(int(1)))
+            ),
+            Ok(1)
+        );
+
+        let index = &storage.indexes[&storage.tables[&table_name()]][&1];
+        assert_eq!(index.get(&int(10)), Some(&vec![int(2)]));
+        assert_eq!(index.get(&int(20)), Some(&vec![int(1)]));
+        assert_eq!(
+            storage.select(&table_name(), value_equal(20)),
+            Ok(vec![vec![int(1), int(20)]])
+        );
+    }
+
+    #[test]
+    fn update_onto_existing_key() {
+        let mut storage = storage_with_rows(&[(1, 10), (2, 10)]);
+        assert_eq!(
+            storage.create_index(&table_name(), "value"),
+            Ok(SqlResult::IndexCreated)
+        );
+
+        assert_eq!(
+            storage.update(
+                &table_name(),
+                ("id".to_owned(), int(2)),
+                Some(Predicate::Equal(int(1)))
+            ),
+            Err(SqlError::DuplicateKey(int(2)))
+        );
+
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(10)]])
+        );
+        let index = &storage.indexes[&storage.tables[&table_name()]][&1];
+        assert_eq!(index.get(&int(10)), Some(&vec![int(1), int(2)]));
+        assert_eq!(
+            storage.select(&table_name(), value_equal(10)),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(10)]])
+        );
+    }
+
+    #[test]
+    fn failed_batch_leaves_index_unchanged() {
+        let mut storage = storage_with_rows(&[(1, 10)]);
+        assert_eq!(
+            storage.create_index(&table_name(), "value"),
+            Ok(SqlResult::IndexCreated)
+        );
+
+        assert_eq!(
+            storage.apply_batch(vec![
+                Op::Insert {
+                    table_name: table_name(),
+                    values: vec![("id".to_owned(), int(2)), ("value".to_owned(), int(10))],
+                },
+                Op::Delete {
+                    table_name: table_name(),
+                    key: int(5),
+                },
+            ]),
+            Err(())
+        );
+
+        assert_eq!(
+            storage.select(&table_name(), value_equal(10)),
+            Ok(vec![vec![int(1), int(10)]])
+        );
+    }
+
+    #[test]
+    fn index_of_missing_column() {
+        let mut storage = storage_with_rows(&[]);
+
+        assert_eq!(
+            storage.create_index(&table_name(), "missing"),
+            Err(SqlError::NotExistentColumn("missing".to_owned()))
+        );
+    }
+}
+
+#[cfg(test)]
+mod transactions {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    fn storage() -> InMemoryStorage {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![("id".to_owned(), StorageType::Integer, HashSet::new())],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        storage
+    }
+
+    fn insert(storage: &mut InMemoryStorage, key: i32) -> Result<SqlResult, SqlError> {
+        storage.insert_into(&table_name(), vec![("id".to_owned(), int(key))])
+    }
+
+    #[test]
+    fn committed_on_ok() {
+        let mut storage = storage();
+
+        assert_eq!(
+            storage.with_transaction(|txn| insert(txn, 1)),
+            Ok(SqlResult::RecordInserted)
+        );
+
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![vec![int(1)]]));
+    }
+
+    #[test]
+    fn rolled_back_on_error() {
+        let mut storage = storage();
+
+        assert_eq!(
+            storage.with_transaction(|txn| {
+                insert(txn, 1)?;
+                txn.create_table(&table_name(), vec![])
+            }),
+            Err(SqlError::TableAlreadyExists)
+        );
+
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![]));
+        assert_eq!(storage.statistics(&table_name()).unwrap().row_count, 0);
+    }
+
+    #[test]
+    fn nested_rollback_keeps_outer_writes() {
+        let mut storage = storage();
+
+        assert_eq!(
+            storage.with_transaction(|outer| {
+                insert(outer, 1)?;
+                assert_eq!(
+                    outer.with_transaction(|inner| {
+                        insert(inner, 2)?;
+                        inner.create_table(&table_name(), vec![])
+                    }),
+                    Err(SqlError::TableAlreadyExists)
+                );
+                insert(outer, 3)
+            }),
+            Ok(SqlResult::RecordInserted)
+        );
+
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1)], vec![int(3)]])
+        );
+    }
+
+    #[test]
+    fn nested_commit_is_undone_by_outer_rollback() {
+        let mut storage = storage();
+
+        assert_eq!(
+            storage.with_transaction(|outer| {
+                outer.with_transaction(|inner| insert(inner, 1))?;
+                outer.create_table(&table_name(), vec![])
+            }),
+            Err(SqlError::TableAlreadyExists)
+        );
+
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![]));
+    }
+}
+
+#[cfg(test)]
+mod batches {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    fn insert(key: i32, value: i32) -> Op {
+        Op::Insert {
+            table_name: table_name(),
+            values: vec![
+                ("id".to_owned(), int(key)),
+                ("value".to_owned(), int(value)),
+            ],
+        }
+    }
+
+    fn storage() -> InMemoryStorage {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), StorageType::Integer, HashSet::new()),
+                    ("value".to_owned(), StorageType::Integer, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        storage
+    }
+
+    #[test]
+    fn apply_batch() {
+        let mut storage = storage();
+
+        assert_eq!(
+            storage.apply_batch(vec![
+                insert(1, 10),
+                insert(2, 20),
+                insert(3, 30),
+                Op::Update {
+                    table_name: table_name(),
+                    key: int(2),
+                    values: vec![("value".to_owned(), int(200))],
+                },
+                Op::Delete {
+                    table_name: table_name(),
+                    key: int(1),
+                },
+            ]),
+            Ok(())
+        );
+
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(2), int(200)], vec![int(3), int(30)]])
+        );
+        assert_eq!(storage.statistics(&table_name()).unwrap().row_count, 2);
+    }
+
+    #[test]
+    fn failed_batch_is_rolled_back() {
+        let mut storage = storage();
+        assert_eq!(storage.apply_batch(vec![insert(1, 10)]), Ok(()));
+        let statistics = storage.statistics(&table_name());
+
+        assert_eq!(
+            storage.apply_batch(vec![
+                insert(2, 20),
+                Op::Delete {
+                    table_name: table_name(),
+                    key: int(1),
+                },
+                Op::Delete {
+                    table_name: table_name(),
+                    key: int(5),
+                },
+            ]),
+            Err(())
+        );
+
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)]])
+        );
+        assert_eq!(storage.statistics(&table_name()), statistics);
+    }
+}
+
+#[cfg(test)]
+mod replacement {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    #[test]
+    fn replace_row_by_key() {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), StorageType::Integer, HashSet::new()),
+                    ("value".to_owned(), StorageType::Integer, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        for (id, value) in &[(1, 10), (2, 20)] {
+            assert_eq!(
+                storage.insert_into(
+                    &table_name(),
+                    vec![
+                        ("id".to_owned(), int(*id)),
+                        ("value".to_owned(), int(*value))
+                    ]
+                ),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+
+        assert_eq!(
+            storage.replace_value(&table_name(), &int(2), vec![int(2), int(200)]),
+            Ok(true)
+        );
+        assert_eq!(
+            storage.replace_value(&table_name(), &int(3), vec![int(3), int(300)]),
+            Ok(false)
+        );
+        assert_eq!(
+            storage.replace_value("other_table", &int(1), vec![int(1), int(100)]),
+            Err(())
+        );
+        assert_eq!(
+            storage.replace_value(&table_name(), &int(1), vec![int(5), int(10)]),
+            Err(())
+        );
+
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(200)]])
+        );
+        assert_eq!(
+            storage.statistics(&table_name()).unwrap().columns[1].max,
+            Some(int(200))
+        );
+    }
+}
+
+#[cfg(test)]
+mod updates {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    #[test]
+    fn update_single_value() {
+        let mut storage = storage_with_rows(&[(1, 10), (2, 20)]);
+
+        assert_eq!(
+            storage.update(
+                &table_name(),
+                ("value".to_owned(), int(200)),
+                Some(Predicate::Equal(int(2)))
+            ),
+            Ok(1)
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(200)]])
+        );
+    }
+
+    #[test]
+    fn update_all_values() {
+        let mut storage = storage_with_rows(&[(1, 10), (2, 20)]);
+
+        assert_eq!(
+            storage.update(&table_name(), ("value".to_owned(), int(0)), None),
+            Ok(2)
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(0)], vec![int(2), int(0)]])
+        );
+        assert_eq!(
+            storage.statistics(&table_name()).unwrap().columns[1].max,
+            Some(int(0))
+        );
+    }
+
+    #[test]
+    fn update_of_missing_column() {
+        let mut storage = storage_with_rows(&[(1, 10), (2, 20)]);
+
+        assert_eq!(
+            storage.update(&table_name(), ("nope".to_owned(), int(5)), None),
+            Err(SqlError::NotExistentColumn("nope".to_owned()))
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(20)]])
+        );
+    }
+
+    #[test]
+    fn update_of_every_key_to_one_value() {
+        let mut storage = storage_with_rows(&[(1, 10), (2, 20), (3, 30)]);
+
+        assert_eq!(
+            storage.update(&table_name(), ("id".to_owned(), int(9)), None),
+            Err(SqlError::DuplicateKey(int(9)))
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![
+                vec![int(1), int(10)],
+                vec![int(2), int(20)],
+                vec![int(3), int(30)]
+            ])
+        );
+        assert_eq!(storage.statistics(&table_name()).unwrap().row_count, 3);
+    }
+
+    #[test]
+    fn update_in_nonexistent_table() {
+        let mut storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.update(&table_name(), ("value".to_owned(), int(0)), None),
+            Err(SqlError::TableDoesNotExists)
+        );
+    }
+}
+
+#[cfg(test)]
+mod deletions {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    #[test]
+    fn delete_single_value() {
+        let mut storage = storage_with_keys(&[1, 2, 3]);
+
+        assert_eq!(
+            storage.delete(&table_name(), Some(Predicate::Equal(int(2)))),
+            Ok(1)
+        );
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1)], vec![int(3)]])
+        );
+    }
+
+    #[test]
+    fn delete_all_values() {
+        let mut storage = storage_with_keys(&[1, 2, 3]);
+
+        assert_eq!(storage.delete(&table_name(), None), Ok(3));
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![]));
+        assert_eq!(
+            storage.statistics(&table_name()).unwrap().columns[0].max,
+            None
+        );
+    }
+
+    #[test]
+    fn delete_from_nonexistent_table() {
+        let mut storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.delete(&table_name(), None),
+            Err(SqlError::TableDoesNotExists)
+        );
+    }
+}
+
+#[cfg(test)]
+mod truncation {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    fn insert_value(storage: &mut InMemoryStorage, value: i32) {
+        assert_eq!(
+            storage.insert_into(&table_name(), vec![("value".to_owned(), int(value))]),
+            Ok(SqlResult::RecordInserted)
+        );
+    }
+
+    #[test]
+    fn truncate_nonexisting_table() {
+        let mut storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.truncate(&table_name()),
+            Err(SqlError::TableDoesNotExists)
+        )
+    }
+
+    #[test]
+    fn truncate_resets_serial_column() {
+        let mut storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), StorageType::Serial, HashSet::new()),
+                    ("value".to_owned(), StorageType::Integer, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+        insert_value(&mut storage, 10);
+        insert_value(&mut storage, 20);
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(20)]])
+        );
+
+        assert_eq!(
+            storage.truncate(&table_name()),
+            Ok(SqlResult::TableTruncated)
+        );
+        assert_eq!(storage.select(&table_name(), None), Ok(vec![]));
+
+        insert_value(&mut storage, 30);
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(30)]])
+        );
+    }
+}
+
+#[cfg(test)]
+mod statistics {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    fn insert_row(storage: &mut InMemoryStorage, id: i32, value: i32) {
+        assert_eq!(
+            storage.insert_into(
+                &table_name(),
+                vec![("id".to_owned(), int(id)), ("value".to_owned(), int(value))]
             ),
             Ok(SqlResult::RecordInserted)
+        );
+    }
+
+    fn value_column(storage: &InMemoryStorage) -> ColumnStatistics {
+        storage.statistics(&table_name()).unwrap().columns[1].clone()
+    }
+
+    #[test]
+    fn statistics_of_nonexisting_table() {
+        let storage = InMemoryStorage::default();
+
+        assert_eq!(
+            storage.statistics(&table_name()),
+            Err(SqlError::TableDoesNotExists)
         )
     }
+
+    #[test]
+    fn min_and_max_follow_row_changes() {
+        let mut storage = InMemoryStorage::default();
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("id".to_owned(), StorageType::Integer, HashSet::new()),
+                    ("value".to_owned(), StorageType::Integer, HashSet::new())
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+
+        assert_eq!(
+            value_column(&storage),
+            ColumnStatistics {
+                name: "value".to_owned(),
+                min: None,
+                max: None,
+                distinct_values: 0
+            }
+        );
+
+        insert_row(&mut storage, 1, 20);
+        insert_row(&mut storage, 2, 10);
+        insert_row(&mut storage, 3, 20);
+        assert_eq!(
+            value_column(&storage),
+            ColumnStatistics {
+                name: "value".to_owned(),
+                min: Some(int(10)),
+                max: Some(int(20)),
+                distinct_values: 2
+            }
+        );
+
+        assert_eq!(
+            storage.replace_value(&table_name(), &int(2), vec![int(2), int(30)]),
+            Ok(true)
+        );
+        assert_eq!(storage.statistics(&table_name()).unwrap().row_count, 3);
+        assert_eq!(
+            value_column(&storage),
+            ColumnStatistics {
+                name: "value".to_owned(),
+                min: Some(int(20)),
+                max: Some(int(30)),
+                distinct_values: 2
+            }
+        );
+
+        assert_eq!(
+            storage.truncate(&table_name()),
+            Ok(SqlResult::TableTruncated)
+        );
+        assert_eq!(
+            value_column(&storage),
+            ColumnStatistics {
+                name: "value".to_owned(),
+                min: None,
+                max: None,
+                distinct_values: 0
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod range_selections {
+    use super::*;
+
+    use super::test_helpers::*;
+
+    fn range(
+        lo: Option<i32>,
+        hi: Option<i32>,
+        lo_inclusive: bool,
+        hi_inclusive: bool,
+    ) -> Predicate {
+        Predicate::Range {
+            lo: lo.map(int),
+            hi: hi.map(int),
+            lo_inclusive,
+            hi_inclusive,
+        }
+    }
+
+    fn keys(values: &[i32]) -> Result<Vec<Vec<Type>>, SqlError> {
+        Ok(values.iter().map(|value| vec![int(*value)]).collect())
+    }
+
+    #[test]
+    fn strict_comparisons() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::LessThan(int(3)))),
+            keys(&[1, 2])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::GreaterThan(int(3)))),
+            keys(&[4, 5])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::GreaterThan(int(5)))),
+            keys(&[])
+        );
+    }
+
+    #[test]
+    fn inclusive_comparisons() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::LessThanOrEqual(int(3)))),
+            keys(&[1, 2, 3])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::GreaterThanOrEqual(int(3)))),
+            keys(&[3, 4, 5])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Not(Box::new(Predicate::LessThanOrEqual(int(3)))))
+            ),
+            keys(&[4, 5])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Not(Box::new(Predicate::GreaterThan(int(3)))))
+            ),
+            keys(&[1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn not_equal() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Not(Box::new(Predicate::Equal(int(3)))))
+            ),
+            keys(&[1, 2, 4, 5])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Not(Box::new(Predicate::Equal(int(6)))))
+            ),
+            keys(&[1, 2, 3, 4, 5])
+        );
+    }
+
+    #[test]
+    fn conjunction() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::And(
+                    Box::new(Predicate::GreaterThan(int(2))),
+                    Box::new(Predicate::LessThan(int(5)))
+                ))
+            ),
+            keys(&[3, 4])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::And(
+                    Box::new(Predicate::Equal(int(1))),
+                    Box::new(Predicate::Equal(int(3)))
+                ))
+            ),
+            keys(&[])
+        );
+    }
+
+    #[test]
+    fn disjunction() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Or(
+                    Box::new(Predicate::Equal(int(3))),
+                    Box::new(Predicate::Equal(int(1)))
+                ))
+            ),
+            keys(&[1, 3])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Or(
+                    Box::new(Predicate::LessThan(int(3))),
+                    Box::new(Predicate::LessThan(int(2)))
+                ))
+            ),
+            keys(&[1, 2])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Or(
+                    Box::new(Predicate::Equal(int(1))),
+                    Box::new(Predicate::And(
+                        Box::new(Predicate::GreaterThan(int(3))),
+                        Box::new(Predicate::LessThan(int(5)))
+                    ))
+                ))
+            ),
+            keys(&[1, 4])
+        );
+    }
+
+    #[test]
+    fn comparisons_with_null() {
+        let storage = storage_with_keys(&[1, 2, 3]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::Equal(Type::Null))),
+            Ok(vec![])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Not(Box::new(Predicate::Equal(Type::Null))))
+            ),
+            Ok(vec![])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::LessThan(Type::Null))),
+            Ok(vec![])
+        );
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Or(
+                    Box::new(Predicate::GreaterThan(Type::Null)),
+                    Box::new(Predicate::Equal(int(2)))
+                ))
+            ),
+            Ok(vec![vec![int(2)]])
+        );
+    }
+
+    #[test]
+    fn comparisons_in_empty_table() {
+        let storage = storage_with_keys(&[]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::LessThanOrEqual(int(3)))),
+            keys(&[])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(Predicate::GreaterThanOrEqual(int(3)))),
+            keys(&[])
+        );
+    }
+
+    #[test]
+    fn inclusive_bounds() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(2), Some(4), true, true))),
+            keys(&[2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn half_open_bounds() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(2), Some(4), true, false))),
+            keys(&[2, 3])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(2), Some(4), false, true))),
+            keys(&[3, 4])
+        );
+    }
+
+    #[test]
+    fn exclusive_bounds() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(2), Some(4), false, false))),
+            keys(&[3])
+        );
+    }
+
+    #[test]
+    fn unbounded_sides() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(range(None, Some(3), false, false))),
+            keys(&[1, 2])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(3), None, true, false))),
+            keys(&[3, 4, 5])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(range(None, None, false, false))),
+            keys(&[1, 2, 3, 4, 5])
+        );
+    }
+
+    #[test]
+    fn empty_ranges() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(3), Some(3), true, false))),
+            keys(&[])
+        );
+        assert_eq!(
+            storage.select(&table_name(), Some(range(Some(4), Some(2), true, true))),
+            keys(&[])
+        );
+    }
+
+    #[test]
+    fn negated_range() {
+        let storage = storage_with_keys(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            storage.select(
+                &table_name(),
+                Some(Predicate::Not(Box::new(range(
+                    Some(2),
+                    Some(4),
+                    true,
+                    false
+                ))))
+            ),
+            keys(&[1, 4, 5])
+        );
+    }
 }
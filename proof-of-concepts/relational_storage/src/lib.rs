@@ -1,11 +1,34 @@
 extern crate types;
 
+mod file_backed;
+mod fsync;
 mod in_memory;
+mod wal;
 
 use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
 
 use crate::types::Type;
 
+pub use wal::WalStorage;
+
+/// storage that writes its tables to the file after every write outside a transaction
+/// and when a transaction is committed, and reads them back when opened again,
+/// a file that can't be read back is an `InvalidData` error
+pub fn file_backed(path: &Path) -> io::Result<Box<dyn Storage>> {
+    Ok(Box::new(file_backed::FileBackedStorage::open(path)?))
+}
+
+/// storage that keeps its tables in memory only
+pub fn in_memory() -> Box<dyn Storage> {
+    Box::new(in_memory::InMemoryStorage::default())
+}
+
 pub trait Storage {
     fn create_table(
         &mut self,
@@ -20,12 +43,114 @@ pub trait Storage {
     ) -> Result<SqlResult, SqlError>;
 
     fn select(
-        &mut self,
+        &self,
         table_name: &String,
         predicate: Option<Predicate>,
-    ) -> Result<Vec<Vec<Type>>, ()>;
+    ) -> Result<Vec<Vec<Type>>, SqlError>;
+
+    /// removes all rows of the table and restarts its serial columns from the beginning
+    fn truncate(&mut self, table_name: &String) -> Result<SqlResult, SqlError>;
+
+    /// per column statistics of the table that are kept up to date as rows are written
+    fn statistics(&self, table_name: &String) -> Result<Statistics, SqlError>;
+
+    /// how often written data is forced to disk, storages kept in memory ignore it
+    fn set_durability_level(&mut self, level: DurabilityLevel);
+
+    /// applies either all writes of the batch or none of them
+    fn apply_batch(&mut self, ops: Vec<Op>) -> Result<(), ()>;
+
+    /// overwrites the row with the key, returns whether there was one;
+    /// a row whose key column holds another key is rejected
+    fn replace_value(&mut self, table_name: &str, key: &Type, value: Vec<Type>)
+        -> Result<bool, ()>;
+
+    /// sets the column of every row matching the predicate, returns how many rows were changed;
+    /// either every row is changed or, e.g. when two rows would get the same key, none is
+    fn update(
+        &mut self,
+        table_name: &str,
+        assignment: (String, Type),
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError>;
+
+    /// removes every row matching the predicate, returns how many rows were removed
+    fn delete(&mut self, table_name: &str, predicate: Option<Predicate>)
+        -> Result<usize, SqlError>;
+
+    /// keeps the keys of rows by the value of the column,
+    /// so that `ColumnEqual` on the column finds them without a scan
+    fn create_index(&mut self, table_name: &str, column_name: &str) -> Result<SqlResult, SqlError>;
+
+    /// starts a transaction, one started in another transaction is nested in it:
+    /// its commit keeps its writes in the outer one and its rollback undoes only them
+    fn begin(&mut self);
+
+    /// keeps the writes of the innermost transaction, a transaction whose writes
+    /// can't be persisted is left open
+    fn commit(&mut self) -> Result<(), SqlError>;
+
+    /// undoes the writes of the innermost transaction, tables created in it included
+    fn rollback(&mut self);
+
+    /// runs the closure in a transaction that is committed when it returns `Ok`
+    /// and rolled back when it returns `Err`, panics or can't be committed
+    fn with_transaction<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<T, E>,
+        E: From<SqlError>,
+    {
+        self.begin();
+        match panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(Ok(value)) => match self.commit() {
+                Ok(()) => Ok(value),
+                Err(error) => {
+                    self.rollback();
+                    Err(error.into())
+                }
+            },
+            Ok(Err(error)) => {
+                self.rollback();
+                Err(error)
+            }
+            Err(payload) => {
+                self.rollback();
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+}
+
+/// a single write of a batch, rows are found by their key, the first column value
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Op {
+    Insert {
+        table_name: String,
+        values: Vec<(String, Type)>,
+    },
+    // only the listed columns are changed
+    Update {
+        table_name: String,
+        key: Type,
+        values: Vec<(String, Type)>,
+    },
+    Delete {
+        table_name: String,
+        key: Type,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DurabilityLevel {
+    // fsync after every write
+    Synchronous,
+    // fsync after every 64 writes or the first write a second after the last fsync,
+    // may lose the latest writes on a crash
+    Periodic,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub enum StorageType {
     // i16
     SmallInt,
@@ -48,9 +173,20 @@ pub enum StorageType {
     // 1 .. i64::MAXs
     BigSerial,
     VarChar,
+    Json,
+    Boolean,
+    // every element is of the boxed type
+    List(Box<StorageType>),
 }
 
 impl StorageType {
+    pub fn is_serial(&self) -> bool {
+        match self {
+            StorageType::SmallSerial | StorageType::Serial | StorageType::BigSerial => true,
+            _ => false,
+        }
+    }
+
     pub fn match_with(&self, sql_type: &Type) -> bool {
         match (self, sql_type) {
             (StorageType::SmallInt, Type::Int(_)) => true,
@@ -63,12 +199,17 @@ impl StorageType {
             (StorageType::Real, Type::Decimal(_)) => true,
             (StorageType::DoublePrecision, Type::Decimal(_)) => true,
             (StorageType::VarChar, Type::VarChar(_)) => true,
+            (StorageType::Json, Type::Json(_)) => true,
+            (StorageType::Boolean, Type::Bool(_)) => true,
+            (StorageType::List(element), Type::List(values)) => {
+                values.iter().all(|value| element.match_with(value))
+            }
             _ => false,
         }
     }
 }
 
-#[derive(Hash, PartialEq, Eq)]
+#[derive(Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Constraint {
     PrimaryKey,
     ForeignKey(String, String),
@@ -77,18 +218,72 @@ pub enum Constraint {
     Check(String, Predicate),
 }
 
-#[derive(Hash, PartialEq, Eq)]
+#[derive(Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Predicate {
     Equal(Type),
     Between(Type, Type),
+    /// keys between optional bounds, e.g. `x >= lo AND x < hi` is
+    /// `Range { lo: Some(lo), hi: Some(hi), lo_inclusive: true, hi_inclusive: false }`
+    Range {
+        lo: Option<Type>,
+        hi: Option<Type>,
+        lo_inclusive: bool,
+        hi_inclusive: bool,
+    },
     In(Vec<Type>),
+    LessThan(Type),
+    LessThanOrEqual(Type),
+    GreaterThan(Type),
+    GreaterThanOrEqual(Type),
+    /// rows the predicate does not select, none when it compares with NULL
     Not(Box<Predicate>),
+    /// keys satisfying both predicates
+    And(Box<Predicate>, Box<Predicate>),
+    /// keys satisfying either predicate
+    Or(Box<Predicate>, Box<Predicate>),
+    /// rows whose value in the named column contains every term ignoring case,
+    /// found by a scan as there is no text index
+    Match(String, Vec<String>),
+    /// rows whose value in the named column matches an SQL `LIKE` pattern, found by a scan
+    Like(String, String),
+    /// rows holding the value in the named column, found by a scan unless the column is indexed
+    ColumnEqual(String, Type),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Statistics {
+    pub row_count: usize,
+    pub columns: Vec<ColumnStatistics>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnStatistics {
+    pub name: String,
+    pub min: Option<Type>,
+    pub max: Option<Type>,
+    pub distinct_values: usize,
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rows: {}", self.row_count)?;
+        for column in &self.columns {
+            writeln!(
+                f,
+                "{}: min {:?}, max {:?}, distinct {}",
+                column.name, column.min, column.max, column.distinct_values
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum SqlResult {
     TableCreated,
     RecordInserted,
+    TableTruncated,
+    IndexCreated,
 }
 
 #[derive(Debug, PartialEq)]
@@ -98,4 +293,10 @@ pub enum SqlError {
     TableDoesNotExists,
     NotExistentColumnInConstrain,
     MismatchedConstraintType,
+    MissingColumnValue(String),
+    NotExistentColumn(String),
+    // a row with the key is in the table already
+    DuplicateKey(Type),
+    // the write was applied but could not be persisted
+    Io(String),
 }
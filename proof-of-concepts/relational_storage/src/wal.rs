@@ -0,0 +1,541 @@
+use super::{
+    Constraint, DurabilityLevel, Op, Predicate, SqlError, SqlResult, Statistics, Storage,
+    StorageType,
+};
+use crate::fsync::Fsync;
+use crate::types::Type;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+
+/// a write of the log, one for every mutating method of `Storage`
+#[derive(Serialize, Deserialize)]
+enum Entry {
+    CreateTable {
+        table_name: String,
+        columns: Vec<(String, StorageType, HashSet<Constraint>)>,
+    },
+    Insert {
+        table_name: String,
+        values: Vec<(String, Type)>,
+    },
+    Truncate {
+        table_name: String,
+    },
+    Batch(Vec<Op>),
+    Replace {
+        table_name: String,
+        key: Type,
+        value: Vec<Type>,
+    },
+    Update {
+        table_name: String,
+        assignment: (String, Type),
+        predicate: Option<Predicate>,
+    },
+    Delete {
+        table_name: String,
+        predicate: Option<Predicate>,
+    },
+    CreateIndex {
+        table_name: String,
+        column_name: String,
+    },
+    Begin,
+    Commit,
+    Rollback,
+}
+
+/// appends every write to the log file before the wrapped storage applies it,
+/// so replaying the log into an empty storage recreates its tables and rows
+pub struct WalStorage<S: Storage> {
+    path: PathBuf,
+    log: File,
+    fsync: Fsync,
+    inner: S,
+}
+
+impl<S: Storage> WalStorage<S> {
+    pub fn open(path: &Path, inner: S) -> io::Result<WalStorage<S>> {
+        let log = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(WalStorage {
+            path: path.to_owned(),
+            log,
+            fsync: Fsync::new(),
+            inner,
+        })
+    }
+
+    /// applies every write of the log to the wrapped storage, returns how many there were;
+    /// an entry cut short by a crash while it was appended ends the log and is cut off,
+    /// so that the next entry is appended right after the last complete one, and
+    /// transactions that were never committed or rolled back are rolled back
+    pub fn replay(&mut self) -> io::Result<usize> {
+        let bytes = fs::read(&self.path)?;
+        let mut reader = Cursor::new(&bytes[..]);
+        let mut replayed = 0;
+        let mut open_transactions = 0;
+        let complete = loop {
+            let complete = reader.position();
+            match bincode::deserialize_from(&mut reader) {
+                Ok(entry) => {
+                    match entry {
+                        Entry::Begin => open_transactions += 1,
+                        Entry::Commit | Entry::Rollback if open_transactions > 0 => {
+                            open_transactions -= 1
+                        }
+                        _ => {}
+                    }
+                    self.apply(entry);
+                    replayed += 1;
+                }
+                Err(error) => match *error {
+                    bincode::ErrorKind::Io(ref error)
+                        if error.kind() == io::ErrorKind::UnexpectedEof =>
+                    {
+                        break complete
+                    }
+                    _ => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+                },
+            }
+        };
+        if complete < bytes.len() as u64 {
+            self.log.set_len(complete)?;
+        }
+        // logged as well, or writes appended from now on would be part of the open transaction
+        for _ in 0..open_transactions {
+            self.append(&Entry::Rollback)?;
+            self.inner.rollback();
+        }
+        Ok(replayed)
+    }
+
+    fn append(&mut self, entry: &Entry) -> io::Result<()> {
+        let bytes = bincode::serialize(entry)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        self.log.write_all(&bytes)?;
+        if self.fsync.due() {
+            self.fsync.sync(&self.log)?;
+        }
+        Ok(())
+    }
+
+    // writes that failed when they were logged fail the same way again
+    fn apply(&mut self, entry: Entry) {
+        match entry {
+            Entry::CreateTable {
+                table_name,
+                columns,
+            } => {
+                let _ = self.inner.create_table(&table_name, columns);
+            }
+            Entry::Insert { table_name, values } => {
+                let _ = self.inner.insert_into(&table_name, values);
+            }
+            Entry::Truncate { table_name } => {
+                let _ = self.inner.truncate(&table_name);
+            }
+            Entry::Batch(ops) => {
+                let _ = self.inner.apply_batch(ops);
+            }
+            Entry::Replace {
+                table_name,
+                key,
+                value,
+            } => {
+                let _ = self.inner.replace_value(&table_name, &key, value);
+            }
+            Entry::Update {
+                table_name,
+                assignment,
+                predicate,
+            } => {
+                let _ = self.inner.update(&table_name, assignment, predicate);
+            }
+            Entry::Delete {
+                table_name,
+                predicate,
+            } => {
+                let _ = self.inner.delete(&table_name, predicate);
+            }
+            Entry::CreateIndex {
+                table_name,
+                column_name,
+            } => {
+                let _ = self.inner.create_index(&table_name, &column_name);
+            }
+            Entry::Begin => self.inner.begin(),
+            Entry::Commit => {
+                let _ = self.inner.commit();
+            }
+            Entry::Rollback => self.inner.rollback(),
+        }
+    }
+}
+
+// entries `Periodic` durability has not synced yet are synced when the log is closed
+impl<S: Storage> Drop for WalStorage<S> {
+    fn drop(&mut self) {
+        if self.fsync.pending() {
+            let _ = self.fsync.sync(&self.log);
+        }
+    }
+}
+
+impl<S: Storage> Storage for WalStorage<S> {
+    fn create_table(
+        &mut self,
+        table_name: &String,
+        columns: Vec<(String, StorageType, HashSet<Constraint>)>,
+    ) -> Result<SqlResult, SqlError> {
+        self.append(&Entry::CreateTable {
+            table_name: table_name.clone(),
+            columns: columns.clone(),
+        })
+        .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.create_table(table_name, columns)
+    }
+
+    fn insert_into(
+        &mut self,
+        table_name: &String,
+        values: Vec<(String, Type)>,
+    ) -> Result<SqlResult, SqlError> {
+        self.append(&Entry::Insert {
+            table_name: table_name.clone(),
+            values: values.clone(),
+        })
+        .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.insert_into(table_name, values)
+    }
+
+    fn select(
+        &self,
+        table_name: &String,
+        predicate: Option<Predicate>,
+    ) -> Result<Vec<Vec<Type>>, SqlError> {
+        self.inner.select(table_name, predicate)
+    }
+
+    fn truncate(&mut self, table_name: &String) -> Result<SqlResult, SqlError> {
+        self.append(&Entry::Truncate {
+            table_name: table_name.clone(),
+        })
+        .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.truncate(table_name)
+    }
+
+    fn statistics(&self, table_name: &String) -> Result<Statistics, SqlError> {
+        self.inner.statistics(table_name)
+    }
+
+    fn set_durability_level(&mut self, level: DurabilityLevel) {
+        self.fsync.set_level(level);
+        self.inner.set_durability_level(level);
+    }
+
+    fn apply_batch(&mut self, ops: Vec<Op>) -> Result<(), ()> {
+        self.append(&Entry::Batch(ops.clone())).map_err(|_| ())?;
+        self.inner.apply_batch(ops)
+    }
+
+    fn replace_value(
+        &mut self,
+        table_name: &str,
+        key: &Type,
+        value: Vec<Type>,
+    ) -> Result<bool, ()> {
+        self.append(&Entry::Replace {
+            table_name: table_name.to_owned(),
+            key: key.clone(),
+            value: value.clone(),
+        })
+        .map_err(|_| ())?;
+        self.inner.replace_value(table_name, key, value)
+    }
+
+    fn update(
+        &mut self,
+        table_name: &str,
+        assignment: (String, Type),
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError> {
+        self.append(&Entry::Update {
+            table_name: table_name.to_owned(),
+            assignment: assignment.clone(),
+            predicate: predicate.clone(),
+        })
+        .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.update(table_name, assignment, predicate)
+    }
+
+    fn delete(
+        &mut self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+    ) -> Result<usize, SqlError> {
+        self.append(&Entry::Delete {
+            table_name: table_name.to_owned(),
+            predicate: predicate.clone(),
+        })
+        .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.delete(table_name, predicate)
+    }
+
+    fn create_index(&mut self, table_name: &str, column_name: &str) -> Result<SqlResult, SqlError> {
+        self.append(&Entry::CreateIndex {
+            table_name: table_name.to_owned(),
+            column_name: column_name.to_owned(),
+        })
+        .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.create_index(table_name, column_name)
+    }
+
+    // boundaries are logged like writes so that replay undoes rolled back transactions too
+    fn begin(&mut self) {
+        let _ = self.append(&Entry::Begin);
+        self.inner.begin();
+    }
+
+    fn commit(&mut self) -> Result<(), SqlError> {
+        self.append(&Entry::Commit)
+            .map_err(|error| SqlError::Io(error.to_string()))?;
+        self.inner.commit()
+    }
+
+    fn rollback(&mut self) {
+        let _ = self.append(&Entry::Rollback);
+        self.inner.rollback();
+    }
+}
+
+#[cfg(test)]
+mod replay {
+    use super::*;
+    use crate::in_memory::InMemoryStorage;
+    use num_bigint::BigInt;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn table_name() -> String {
+        "table_name".to_owned()
+    }
+
+    fn int(value: i32) -> Type {
+        Type::Int(BigInt::from(value))
+    }
+
+    fn row(key: i32, value: i32) -> Vec<(String, Type)> {
+        vec![
+            ("key".to_owned(), int(key)),
+            ("value".to_owned(), int(value)),
+        ]
+    }
+
+    #[test]
+    fn replay_restores_writes_lost_in_a_crash() {
+        let path = env::temp_dir().join(format!("replay-{}.wal", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let mut storage =
+                WalStorage::open(&path, InMemoryStorage::default()).expect("log is opened");
+            assert_eq!(
+                storage.create_table(
+                    &table_name(),
+                    vec![
+                        ("key".to_owned(), StorageType::Integer, HashSet::new()),
+                        ("value".to_owned(), StorageType::Integer, HashSet::new()),
+                    ],
+                ),
+                Ok(SqlResult::TableCreated)
+            );
+            for (key, value) in &[(1, 10), (2, 20), (3, 30)] {
+                assert_eq!(
+                    storage.insert_into(&table_name(), row(*key, *value)),
+                    Ok(SqlResult::RecordInserted)
+                );
+            }
+            assert_eq!(
+                storage.update(
+                    &table_name(),
+                    ("value".to_owned(), int(200)),
+                    Some(Predicate::Equal(int(2)))
+                ),
+                Ok(1)
+            );
+            assert_eq!(
+                storage.delete(&table_name(), Some(Predicate::Equal(int(3)))),
+                Ok(1)
+            );
+            // the in memory storage is dropped with the rows that were never flushed anywhere
+        }
+
+        let mut storage =
+            WalStorage::open(&path, InMemoryStorage::default()).expect("log is reopened");
+        assert_eq!(storage.replay().expect("log is replayed"), 6);
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(2), int(200)]])
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    fn create_table(storage: &mut WalStorage<InMemoryStorage>) {
+        assert_eq!(
+            storage.create_table(
+                &table_name(),
+                vec![
+                    ("key".to_owned(), StorageType::Integer, HashSet::new()),
+                    ("value".to_owned(), StorageType::Integer, HashSet::new()),
+                ],
+            ),
+            Ok(SqlResult::TableCreated)
+        );
+    }
+
+    #[test]
+    fn periodic_durability_syncs_once_in_a_while() {
+        let path = env::temp_dir().join(format!("periodic-{}.wal", process::id()));
+        let _ = fs::remove_file(&path);
+        let mut storage =
+            WalStorage::open(&path, InMemoryStorage::default()).expect("log is opened");
+        let syncs = storage.fsync.record();
+        create_table(&mut storage);
+        assert_eq!(
+            storage.insert_into(&table_name(), row(0, 0)),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(syncs.get(), 2);
+
+        storage.set_durability_level(DurabilityLevel::Periodic);
+        for key in 1..crate::fsync::PERIODIC_WRITES as i32 {
+            assert_eq!(
+                storage.insert_into(&table_name(), row(key, key)),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        assert_eq!(syncs.get(), 2);
+        assert_eq!(
+            storage.insert_into(&table_name(), row(-1, -1)),
+            Ok(SqlResult::RecordInserted)
+        );
+        assert_eq!(syncs.get(), 3);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn periodic_entries_are_synced_when_dropped() {
+        let path = env::temp_dir().join(format!("dropped-{}.wal", process::id()));
+        let _ = fs::remove_file(&path);
+        let syncs = {
+            let mut storage =
+                WalStorage::open(&path, InMemoryStorage::default()).expect("log is opened");
+            storage.set_durability_level(DurabilityLevel::Periodic);
+            let syncs = storage.fsync.record();
+            create_table(&mut storage);
+            assert_eq!(
+                storage.insert_into(&table_name(), row(1, 10)),
+                Ok(SqlResult::RecordInserted)
+            );
+            assert_eq!(syncs.get(), 0);
+            syncs
+        };
+        assert_eq!(syncs.get(), 1);
+
+        let mut storage =
+            WalStorage::open(&path, InMemoryStorage::default()).expect("log is reopened");
+        assert_eq!(storage.replay().expect("log is replayed"), 2);
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)]])
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn entry_cut_short_is_cut_off() {
+        let path = env::temp_dir().join(format!("cut-short-{}.wal", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let mut storage =
+                WalStorage::open(&path, InMemoryStorage::default()).expect("log is opened");
+            create_table(&mut storage);
+            assert_eq!(
+                storage.insert_into(&table_name(), row(1, 10)),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+        let complete = fs::metadata(&path).expect("log is written").len();
+        let entry = bincode::serialize(&Entry::Insert {
+            table_name: table_name(),
+            values: row(2, 20),
+        })
+        .expect("entry is serialized");
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut log| log.write_all(&entry[..entry.len() / 2]))
+            .expect("half of an entry is appended");
+
+        {
+            let mut storage =
+                WalStorage::open(&path, InMemoryStorage::default()).expect("log is reopened");
+            assert_eq!(storage.replay().expect("log is replayed"), 2);
+            assert_eq!(fs::metadata(&path).expect("log is kept").len(), complete);
+            assert_eq!(
+                storage.insert_into(&table_name(), row(3, 30)),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+
+        let mut storage =
+            WalStorage::open(&path, InMemoryStorage::default()).expect("log is reopened");
+        assert_eq!(storage.replay().expect("log is replayed"), 3);
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(1), int(10)], vec![int(3), int(30)]])
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn open_transaction_is_rolled_back() {
+        let path = env::temp_dir().join(format!("open-transaction-{}.wal", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let mut storage =
+                WalStorage::open(&path, InMemoryStorage::default()).expect("log is opened");
+            create_table(&mut storage);
+            storage.begin();
+            assert_eq!(
+                storage.insert_into(&table_name(), row(1, 10)),
+                Ok(SqlResult::RecordInserted)
+            );
+            // dropped before the transaction is committed, as in a crash
+        }
+
+        {
+            let mut storage =
+                WalStorage::open(&path, InMemoryStorage::default()).expect("log is reopened");
+            assert_eq!(storage.replay().expect("log is replayed"), 3);
+            assert_eq!(storage.inner.open_transactions(), 0);
+            assert_eq!(storage.select(&table_name(), None), Ok(vec![]));
+            assert_eq!(
+                storage.insert_into(&table_name(), row(2, 20)),
+                Ok(SqlResult::RecordInserted)
+            );
+        }
+
+        let mut storage =
+            WalStorage::open(&path, InMemoryStorage::default()).expect("log is reopened");
+        storage.replay().expect("log is replayed");
+        assert_eq!(
+            storage.select(&table_name(), None),
+            Ok(vec![vec![int(2), int(20)]])
+        );
+        let _ = fs::remove_file(&path);
+    }
+}
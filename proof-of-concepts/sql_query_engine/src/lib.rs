@@ -1,30 +1,44 @@
 extern crate types;
 
-use std::collections::{BTreeMap, HashMap};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display};
 use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use sqlparser::ast::{
-    Assignment, BinaryOperator, Expr, Query, Select, SetExpr, Statement, TableFactor,
-    TableWithJoins,
+    Assignment, BinaryOperator, ColumnDef, ColumnOption, ColumnOptionDef, Cte, DataType, Expr,
+    Function, Join, JoinConstraint, JoinOperator, OrderByExpr, Query, Select, SelectItem, SetExpr,
+    Statement, TableConstraint, TableFactor, TableWithJoins, UnaryOperator, Value, Values,
 };
 use sqlparser::dialect::GenericDialect;
 use sqlparser::parser::Parser;
 
+use bigdecimal::BigDecimal;
 use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use relational_storage::{Constraint, Op, Predicate, SqlError, Storage, StorageType};
 use serde::export::Formatter;
 use types::{Type, TypeError};
 
+pub use types::TypeTag;
+
 pub type ExecutionResult = Result<EngineEvent, ErrorEvent>;
 
 #[derive(Debug, PartialEq)]
 pub enum EngineEvent {
     TableCreated(String),
-    RecordInserted,
-    RecordsSelected(Vec<Vec<u8>>),
-    RecordsUpdated,
-    RecordsDeleted,
+    RecordsInserted(usize),
+    RecordsSelected(Vec<Row>),
+    RecordsUpdated(usize),
+    RecordsDeleted(usize),
+    IndexCreated(String),
+    QueryPlan(String),
+    TransactionStarted,
+    TransactionCommitted,
+    TransactionRolledBack,
 }
 
 #[derive(Debug, PartialEq)]
@@ -32,6 +46,25 @@ pub enum ErrorEvent {
     TableAlreadyExists(String),
     UnimplementedBranch(String),
     TableDoesNotExist(String),
+    TypeMismatch(String),
+    ColumnDoesNotExist(String),
+    EncodingError(String),
+    WriteRejected(String),
+    SubqueryReturnedMultipleRows(String),
+    ColumnNotGrouped(String),
+    ScanLimitExceeded(usize),
+    ColumnCountMismatch(String),
+    ParseFailure(String),
+    InvalidParameterPosition(String),
+    ExpressionTooComplex(usize),
+    SchemaConflict(String),
+    ParseError(String),
+    UnsupportedStatement { kind: String },
+    UnsupportedOperator(String),
+    TransactionState(String),
+    DuplicateKey(String),
+    StorageError(String),
+    IndexLimitExceeded(usize),
 }
 
 impl Display for ErrorEvent {
@@ -40,945 +73,6821 @@ impl Display for ErrorEvent {
             ErrorEvent::TableAlreadyExists(table_name) => write!(f, "{}", table_name),
             ErrorEvent::UnimplementedBranch(error) => write!(f, "{}", error),
             ErrorEvent::TableDoesNotExist(table_name) => write!(f, "{}", table_name),
+            ErrorEvent::TypeMismatch(error) => write!(f, "{}", error),
+            ErrorEvent::ColumnDoesNotExist(column_name) => write!(f, "{}", column_name),
+            ErrorEvent::EncodingError(error) => write!(f, "{}", error),
+            ErrorEvent::WriteRejected(reason) => write!(f, "{}", reason),
+            ErrorEvent::SubqueryReturnedMultipleRows(subquery) => write!(f, "{}", subquery),
+            ErrorEvent::ColumnNotGrouped(column_name) => write!(f, "{}", column_name),
+            ErrorEvent::ScanLimitExceeded(limit) => write!(f, "more than {} rows scanned", limit),
+            ErrorEvent::ColumnCountMismatch(error) => write!(f, "{}", error),
+            ErrorEvent::ParseFailure(error) => write!(f, "{}", error),
+            ErrorEvent::InvalidParameterPosition(parameter) => {
+                write!(f, "{} cannot name a table", parameter)
+            }
+            ErrorEvent::ExpressionTooComplex(depth) => {
+                write!(f, "expression is nested deeper than {} levels", depth)
+            }
+            ErrorEvent::SchemaConflict(difference) => write!(f, "{}", difference),
+            ErrorEvent::ParseError(error) => write!(f, "{}", error),
+            ErrorEvent::UnsupportedStatement { kind } => {
+                write!(f, "{} statements are not supported", kind)
+            }
+            ErrorEvent::UnsupportedOperator(operator) => {
+                write!(f, "operator {} is not supported", operator)
+            }
+            ErrorEvent::TransactionState(error) => write!(f, "{}", error),
+            ErrorEvent::DuplicateKey(error) => write!(f, "{}", error),
+            ErrorEvent::StorageError(error) => write!(f, "{}", error),
+            ErrorEvent::IndexLimitExceeded(limit) => {
+                write!(f, "a table can't have more than {} indexes", limit)
+            }
+        }
+    }
+}
+
+impl From<TypeError> for ErrorEvent {
+    fn from(error: TypeError) -> Self {
+        match error {
+            TypeError::Unsupported(message) => ErrorEvent::UnimplementedBranch(message),
+            TypeError::Mismatch(message) => ErrorEvent::TypeMismatch(message),
+            TypeError::Encoding(message) => ErrorEvent::EncodingError(message),
+            TypeError::ParseFailure { raw, target } => {
+                ErrorEvent::ParseFailure(format!("{} is not a valid {:?}", raw, target))
+            }
+        }
+    }
+}
+
+/// rules of comparing strings in predicates and while sorting
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Collation {
+    Binary,
+    CaseInsensitive,
+}
+
+impl Collation {
+    pub fn compare(self, left: &Type, right: &Type) -> Ordering {
+        match (self, left, right) {
+            (Collation::CaseInsensitive, Type::VarChar(left), Type::VarChar(right)) => {
+                left.to_lowercase().cmp(&right.to_lowercase())
+            }
+            // integers are widened to compare with decimals by value rather than by type
+            (_, Type::Int(left), Type::Decimal(right)) => BigDecimal::from(left.clone()).cmp(right),
+            (_, Type::Decimal(left), Type::Int(right)) => {
+                left.cmp(&BigDecimal::from(right.clone()))
+            }
+            _ => left.cmp(right),
+        }
+    }
+}
+
+/// kind of values a column of the type holds
+fn tag_of(data_type: &DataType) -> Result<TypeTag, ErrorEvent> {
+    match data_type {
+        DataType::SmallInt | DataType::Int | DataType::BigInt => Ok(TypeTag::Int),
+        DataType::Decimal(_, _) | DataType::Float(_) | DataType::Real | DataType::Double => {
+            Ok(TypeTag::Decimal)
+        }
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Text => Ok(TypeTag::VarChar),
+        DataType::Custom(name) if name.to_string().eq_ignore_ascii_case("json") => {
+            Ok(TypeTag::Json)
+        }
+        DataType::Array(_) => Ok(TypeTag::List),
+        DataType::Boolean => Ok(TypeTag::Bool),
+        data_type => Err(ErrorEvent::UnimplementedBranch(format!(
+            "UNIMPLEMENTED HANDLING OF COLUMN TYPE \n{}\n",
+            data_type
+        ))),
+    }
+}
+
+/// type of the storage column holding values of a column of the type
+fn storage_type_of(data_type: &DataType) -> Result<StorageType, ErrorEvent> {
+    match data_type {
+        DataType::SmallInt => Ok(StorageType::SmallInt),
+        DataType::Int => Ok(StorageType::Integer),
+        DataType::BigInt => Ok(StorageType::BigInt),
+        DataType::Decimal(_, _) => Ok(StorageType::Decimal),
+        DataType::Float(_) | DataType::Double => Ok(StorageType::DoublePrecision),
+        DataType::Real => Ok(StorageType::Real),
+        DataType::Array(element) => Ok(StorageType::List(Box::new(storage_type_of(element)?))),
+        data_type => match tag_of(data_type)? {
+            TypeTag::Json => Ok(StorageType::Json),
+            TypeTag::Bool => Ok(StorageType::Boolean),
+            _ => Ok(StorageType::VarChar),
+        },
+    }
+}
+
+/// the error of the engine a write or read of the table in the storage failed with
+fn storage_error(table_name: &str, error: SqlError) -> ErrorEvent {
+    match error {
+        SqlError::TableDoesNotExists => ErrorEvent::TableDoesNotExist(table_name.to_owned()),
+        SqlError::NotExistentColumn(column_name) => ErrorEvent::ColumnDoesNotExist(column_name),
+        SqlError::DuplicateKey(key) => ErrorEvent::DuplicateKey(format!(
+            "{} already has a row with key {:?}",
+            table_name, key
+        )),
+        error => ErrorEvent::StorageError(format!("{} can't be written: {:?}", table_name, error)),
+    }
+}
+
+/// rows of a table along with their keys
+type KeyedRows = Vec<(BigInt, Vec<Type>)>;
+
+/// rows of the table the storage selects by the predicate, split into their key and values
+fn stored(
+    table_name: &str,
+    storage: &dyn Storage,
+    predicate: Option<Predicate>,
+) -> Result<KeyedRows, ErrorEvent> {
+    let rows = storage
+        .select(&table_name.to_owned(), predicate)
+        .map_err(|error| storage_error(table_name, error))?;
+    Ok(rows
+        .into_iter()
+        .map(|mut row| match row.remove(0) {
+            Type::Int(key) => (key, row),
+            key => unreachable!("{:?} is not a key written by the engine", key),
+        })
+        .collect())
+}
+
+/// column of a table in the storage holding the key of the row,
+/// followed by the columns of the table
+const KEY_COLUMN: &str = "#key";
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum When {
+    Before,
+    After,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WriteEvent {
+    Insert(Vec<Type>),
+    Update { old: Vec<Type>, new: Vec<Type> },
+    Delete(Vec<Type>),
+}
+
+/// selected records together with the names of their columns
+#[derive(Debug, PartialEq)]
+pub struct Rows {
+    columns: Arc<[String]>,
+    records: Vec<Row>,
+}
+
+impl Rows {
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Row> {
+        self.records.iter()
+    }
+}
+
+/// values of a selected record with typed access by column index or name,
+/// rows are equal when their values are whatever their columns are named
+#[derive(Debug, Clone)]
+pub struct Row {
+    columns: Arc<[String]>,
+    values: Vec<Type>,
+}
+
+impl Row {
+    /// rows of the records sharing the names of their columns
+    fn all(columns: Arc<[String]>, records: Vec<Vec<Type>>) -> Vec<Row> {
+        records
+            .into_iter()
+            .map(|values| Row {
+                columns: columns.clone(),
+                values,
+            })
+            .collect()
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn values(&self) -> &[Type] {
+        &self.values
+    }
+
+    pub fn get(&self, index: usize) -> Result<&Type, ErrorEvent> {
+        self.values
+            .get(index)
+            .ok_or_else(|| ErrorEvent::ColumnDoesNotExist(format!("column at index {}", index)))
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Result<&Type, ErrorEvent> {
+        match self.columns.iter().position(|column| column == name) {
+            Some(index) => self.get(index),
+            None => Err(ErrorEvent::ColumnDoesNotExist(name.to_owned())),
+        }
+    }
+
+    pub fn get_int(&self, index: usize) -> Result<&BigInt, ErrorEvent> {
+        match self.get(index)? {
+            Type::Int(value) => Ok(value),
+            value => Err(Row::mismatch(index, value, TypeTag::Int)),
+        }
+    }
+
+    /// an integer that fits into `i64`, such as the result of COUNT
+    pub fn get_i64(&self, index: usize) -> Result<i64, ErrorEvent> {
+        let value = self.get_int(index)?;
+        value.to_i64().ok_or_else(|| {
+            ErrorEvent::TypeMismatch(format!(
+                "column at index {} holds {} which does not fit into i64",
+                index, value
+            ))
+        })
+    }
+
+    pub fn get_decimal(&self, index: usize) -> Result<&BigDecimal, ErrorEvent> {
+        match self.get(index)? {
+            Type::Decimal(value) => Ok(value),
+            value => Err(Row::mismatch(index, value, TypeTag::Decimal)),
+        }
+    }
+
+    pub fn get_bool(&self, index: usize) -> Result<bool, ErrorEvent> {
+        match self.get(index)? {
+            Type::Bool(value) => Ok(*value),
+            value => Err(Row::mismatch(index, value, TypeTag::Bool)),
+        }
+    }
+
+    pub fn get_str(&self, index: usize) -> Result<&str, ErrorEvent> {
+        match self.get(index)? {
+            Type::VarChar(value) => Ok(value),
+            value => Err(Row::mismatch(index, value, TypeTag::VarChar)),
+        }
+    }
+
+    fn mismatch(index: usize, value: &Type, expected: TypeTag) -> ErrorEvent {
+        ErrorEvent::TypeMismatch(format!(
+            "column at index {} holds {:?}, not {:?}",
+            index,
+            value.tag(),
+            expected
+        ))
+    }
+}
+
+impl PartialEq for Row {
+    fn eq(&self, other: &Row) -> bool {
+        self.values == other.values
+    }
+}
+
+/// a row of values with no column names, which can be accessed by index only
+impl From<Vec<Type>> for Row {
+    fn from(values: Vec<Type>) -> Row {
+        Row {
+            columns: Arc::from(vec![]),
+            values,
         }
     }
 }
 
+type WriteHook = Box<dyn FnMut(&WriteEvent) -> Result<(), ErrorEvent>>;
+
+/// levels a WHERE clause may have unless configured otherwise
+const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 64;
+
+/// custom binary operators by their lowercase name
+type Operators = HashMap<String, Box<dyn Fn(&Type, &Type) -> bool>>;
+
 pub struct Engine {
     dialect: GenericDialect,
-    tables: HashMap<String, BTreeMap<BigInt, Vec<u8>>>,
+    tables: HashMap<String, Table>,
+    collation: Collation,
+    operators: Operators,
+    hooks: HashMap<(String, When), Vec<WriteHook>>,
+    cache: Option<QueryCache>,
+    max_scanned_rows: Option<usize>,
+    max_expression_depth: usize,
+    max_indexes: Option<usize>,
+    // tables as they were when the open transaction began, restored on ROLLBACK
+    transaction: Option<HashMap<String, Table>>,
+    // rows of the declared tables, the tables above hold their definitions
+    storage: Box<dyn Storage>,
 }
 
-impl Engine {
-    #[allow(clippy::cognitive_complexity)] // TODO simplify SQL execution
-    pub fn execute(&mut self, sql: String) -> ExecutionResult {
-        let mut statements = match Parser::parse_sql(&self.dialect, sql) {
-            Ok(ok) => ok,
-            Err(error) => return Err(ErrorEvent::UnimplementedBranch(format!("{:?}", error))),
+/// results of the least recently run queries keyed by their normalized text
+struct QueryCache {
+    capacity: usize,
+    // the most recently used entry is the last one
+    entries: VecDeque<(String, Query, Vec<Row>)>,
+}
+
+impl QueryCache {
+    fn get(&mut self, key: &str) -> Option<Vec<Row>> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(cached, _, _)| cached == key)?;
+        let entry = self.entries.remove(index)?;
+        let records = entry.2.clone();
+        self.entries.push_back(entry);
+        Some(records)
+    }
+
+    fn put(&mut self, key: String, query: Query, records: Vec<Row>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, query, records));
+    }
+
+    /// evicts results of every query that reads from the table
+    fn invalidate(&mut self, table_name: &str) {
+        self.entries
+            .retain(|(_, query, _)| !query_references(query, table_name));
+    }
+}
+
+#[derive(Clone, Default)]
+struct Table {
+    columns: Vec<String>,
+    // declared column types and options, empty for tables materialized from a query
+    types: Vec<DataType>,
+    options: Vec<Vec<ColumnOption>>,
+    // rows of a table materialized from a query, rows of a declared table are kept
+    // in the storage keyed by `next_key` in the order they were inserted
+    materialized: Option<Vec<Vec<Type>>>,
+    next_key: BigInt,
+    // positions of the columns of a PRIMARY KEY (a, b) constraint
+    // and the key of the row holding every tuple of their values
+    primary_key: Vec<usize>,
+    primary_keys: BTreeMap<Vec<Type>, BigInt>,
+    // positions of the columns the storage keeps an index of
+    indexes: Vec<usize>,
+}
+
+impl Table {
+    /// writes a row of values given for the named columns, or for all of them if none are named
+    fn insert(
+        &mut self,
+        table_name: &str,
+        columns: &[String],
+        row: Vec<Type>,
+        hooks: &mut HashMap<(String, When), Vec<WriteHook>>,
+        storage: &mut dyn Storage,
+    ) -> Result<(), ErrorEvent> {
+        let row = if columns.is_empty() {
+            row
+        } else {
+            self.arrange(columns, row)?
         };
-        match statements.pop() {
-            Some(Statement::CreateTable { name, .. }) => {
-                let table_name = name.to_string();
-                if self.tables.contains_key(&table_name) {
-                    Err(ErrorEvent::TableAlreadyExists(table_name))
-                } else {
-                    self.tables.insert(table_name.clone(), BTreeMap::new());
-                    Ok(EngineEvent::TableCreated(table_name))
-                }
+        if !self.types.is_empty() && row.len() != self.types.len() {
+            return Err(ErrorEvent::ColumnCountMismatch(format!(
+                "{} has {} columns but {} values were given",
+                table_name,
+                self.types.len(),
+                row.len()
+            )));
+        }
+        let mut record = vec![];
+        for (index, value) in row.into_iter().enumerate() {
+            let value = coerce(value, self.types.get(index))?;
+            check_type(&value, &self.columns[index], self.types.get(index))?;
+            record.push(value);
+        }
+        let key = self.next_key.clone();
+        self.next_key = &key + BigInt::from(1);
+        let tuple = if self.primary_key.is_empty() {
+            None
+        } else {
+            let tuple = self.tuple_of(&record)?;
+            if self.primary_keys.contains_key(&tuple) {
+                return Err(duplicate_key(table_name, &tuple));
             }
-            Some(Statement::Insert {
-                table_name, source, ..
-            }) => {
-                let table_name = table_name.to_string();
-                match self.tables.get_mut(&table_name) {
-                    None => Err(ErrorEvent::TableDoesNotExist(table_name)),
-                    Some(table) => {
-                        let Query { body, .. } = &*source;
-                        if let SetExpr::Values(values) = &body {
-                            let values = &values.0;
-                            if let Expr::Value(value) = &values[0][0] {
-                                if let Ok(Type::Int(value)) = Type::try_from(value.clone()) {
-                                    let encoded = bincode::serialize(&value).unwrap();
-                                    table.insert(value, encoded);
-                                    Ok(EngineEvent::RecordInserted)
-                                } else {
-                                    Err(
-                                        ErrorEvent::UnimplementedBranch(
-                                            format!(
-                                                "UNIMPLEMENTED HANDLING OF STRING PARSING \n{:?}\n IN \"INSERT INTO <table> VALUES (v)\"",
-                                                value
-                                            )
-                                        )
-                                    )
-                                }
-                            } else {
-                                Err(
-                                    ErrorEvent::UnimplementedBranch(
-                                        format!(
-                                            "UNIMPLEMENTED HANDLING OF PARSING \n{:?}\n IN \"INSERT INTO <table> VALUES (v)\"",
-                                            values
-                                        )
-                                    )
-                                )
-                            }
-                        } else {
-                            Err(ErrorEvent::UnimplementedBranch(format!(
-                                "UNIMPLEMENTED HANDLING OF VALUES INSERTION \n{:?}\n",
-                                source
-                            )))
+            Some(tuple)
+        };
+        let event = WriteEvent::Insert(record);
+        fire(hooks, table_name, When::Before, &[&event])?;
+        if let WriteEvent::Insert(record) = &event {
+            let mut values = vec![(KEY_COLUMN.to_owned(), Type::Int(key.clone()))];
+            values.extend(self.columns.iter().cloned().zip(record.iter().cloned()));
+            storage
+                .insert_into(&table_name.to_owned(), values)
+                .map_err(|error| storage_error(table_name, error))?;
+        }
+        if let Some(tuple) = tuple {
+            self.primary_keys.insert(tuple, key);
+        }
+        fire(hooks, table_name, When::After, &[&event])
+    }
+
+    /// values of the primary key columns of the record, none of them can be NULL
+    fn tuple_of(&self, record: &[Type]) -> Result<Vec<Type>, ErrorEvent> {
+        let mut tuple = vec![];
+        for index in &self.primary_key {
+            if record[*index] == Type::Null {
+                return Err(ErrorEvent::TypeMismatch(format!(
+                    "key column {} can't hold NULL",
+                    self.columns[*index]
+                )));
+            }
+            tuple.push(record[*index].clone());
+        }
+        Ok(tuple)
+    }
+
+    /// rejects updates after which two rows would have the same key or primary key values
+    fn check_updates(
+        &self,
+        table_name: &str,
+        updates: &[(BigInt, WriteEvent)],
+        storage: &dyn Storage,
+    ) -> Result<(), ErrorEvent> {
+        if self.primary_key.is_empty() {
+            return Ok(());
+        }
+        let updated = updates
+            .iter()
+            .filter_map(|(key, event)| match event {
+                WriteEvent::Update { new, .. } => Some((key, new)),
+                _ => None,
+            })
+            .collect::<BTreeMap<&BigInt, &Vec<Type>>>();
+        let mut tuples = BTreeSet::new();
+        for (key, record) in &self.rows(table_name, storage)? {
+            let record = updated.get(key).copied().unwrap_or(record);
+            let tuple = self.tuple_of(record)?;
+            if tuples.contains(&tuple) {
+                return Err(duplicate_key(table_name, &tuple));
+            }
+            tuples.insert(tuple);
+        }
+        Ok(())
+    }
+
+    /// rebuilds the keys of primary key values after rows were rewritten or removed
+    fn reindex(&mut self, table_name: &str, storage: &dyn Storage) -> Result<(), ErrorEvent> {
+        if self.primary_key.is_empty() {
+            return Ok(());
+        }
+        let primary_keys = self
+            .rows(table_name, storage)?
+            .into_iter()
+            .map(|(key, record)| {
+                (
+                    self.primary_key
+                        .iter()
+                        .map(|index| record[*index].clone())
+                        .collect(),
+                    key,
+                )
+            })
+            .collect();
+        self.primary_keys = primary_keys;
+        Ok(())
+    }
+
+    /// rows of the table with their keys in the order they were inserted
+    fn rows(&self, table_name: &str, storage: &dyn Storage) -> Result<KeyedRows, ErrorEvent> {
+        match &self.materialized {
+            Some(records) => Ok(records
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(key, record)| (BigInt::from(key), record))
+                .collect()),
+            None => stored(table_name, storage, None),
+        }
+    }
+
+    /// the primary key values of the only row an equality on every primary key column,
+    /// joined by AND, can select; strings are compared as they are only with a binary collation
+    fn key_lookup(&self, condition: &Expr, collation: Collation) -> Option<Vec<Type>> {
+        if self.primary_key.is_empty() || collation != Collation::Binary {
+            return None;
+        }
+        let mut given = BTreeMap::new();
+        let mut conditions = vec![condition];
+        while let Some(condition) = conditions.pop() {
+            match condition {
+                Expr::BinaryOp {
+                    left,
+                    op: BinaryOperator::And,
+                    right,
+                } => {
+                    conditions.push(left);
+                    conditions.push(right);
+                }
+                Expr::BinaryOp {
+                    left,
+                    op: BinaryOperator::Eq,
+                    right,
+                } => match (left.deref(), right.deref()) {
+                    (Expr::Identifier(column), Expr::Value(value)) => {
+                        let index = self.column_index(column).ok()?;
+                        let value = Type::try_from(value.clone()).ok()?;
+                        if given
+                            .insert(index, coerce(value, self.types.get(index)).ok()?)
+                            .is_some()
+                        {
+                            return None;
                         }
                     }
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+        if given.len() != self.primary_key.len() {
+            return None;
+        }
+        self.primary_key
+            .iter()
+            .map(|index| given.remove(index))
+            .collect()
+    }
+
+    /// puts values given for the named columns in the order of the table columns,
+    /// columns that are not named get their default
+    fn arrange(&self, columns: &[String], values: Vec<Type>) -> Result<Vec<Type>, ErrorEvent> {
+        if columns.len() != values.len() {
+            return Err(ErrorEvent::ColumnCountMismatch(format!(
+                "{} columns were named but {} values were given",
+                columns.len(),
+                values.len()
+            )));
+        }
+        let mut given = vec![None; self.columns.len()];
+        for (column_name, value) in columns.iter().zip(values) {
+            given[self.column_index(column_name)?] = Some(value);
+        }
+        let mut row = vec![];
+        for (index, value) in given.into_iter().enumerate() {
+            let default = self.options.get(index).and_then(|options| {
+                options.iter().find_map(|option| match option {
+                    ColumnOption::Default(expr) => Some(expr),
+                    _ => None,
+                })
+            });
+            row.push(match (value, default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => evaluate(default, &Scope::default(), &[])?,
+                (None, None) => {
+                    return Err(ErrorEvent::ColumnCountMismatch(format!(
+                        "no value was given for column {} without a default",
+                        self.columns[index]
+                    )))
+                }
+            });
+        }
+        Ok(row)
+    }
+
+    /// the storage predicate selecting the same rows as the condition if there is one:
+    /// equalities of a column and a value joined by AND or OR,
+    /// strings are compared as they are only with a binary collation
+    fn predicate(
+        &self,
+        condition: &Expr,
+        scope: &Scope,
+        collation: Collation,
+    ) -> Option<Predicate> {
+        match condition {
+            Expr::Nested(condition) => self.predicate(condition, scope, collation),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => Some(Predicate::And(
+                Box::new(self.predicate(left, scope, collation)?),
+                Box::new(self.predicate(right, scope, collation)?),
+            )),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Or,
+                right,
+            } => Some(Predicate::Or(
+                Box::new(self.predicate(left, scope, collation)?),
+                Box::new(self.predicate(right, scope, collation)?),
+            )),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Eq,
+                right,
+            } => {
+                let (column, value) = match (left.deref(), right.deref()) {
+                    (Expr::Value(value), column) | (column, Expr::Value(value)) => (column, value),
+                    _ => return None,
+                };
+                let index = scope.resolve(column).ok()?;
+                let data_type = self.types.get(index)?;
+                let value = coerce(Type::try_from(value.clone()).ok()?, Some(data_type)).ok()?;
+                match (tag_of(data_type).ok()?, &value) {
+                    (TypeTag::Int, Type::Int(_)) | (TypeTag::Bool, Type::Bool(_)) => {}
+                    (TypeTag::VarChar, Type::VarChar(_)) if collation == Collation::Binary => {}
+                    _ => return None,
                 }
+                Some(Predicate::ColumnEqual(self.columns[index].clone(), value))
             }
-            Some(Statement::Update {
-                table_name,
-                assignments,
-                selection,
-            }) => {
-                let table_name = table_name.to_string();
-                match self.tables.get_mut(&table_name) {
-                    None => Err(ErrorEvent::TableDoesNotExist(table_name)),
-                    Some(table) => {
-                        let keys = match selection {
-                            Some(Expr::BinaryOp { right, .. }) => {
-                                if let Expr::Value(value) = right.deref() {
-                                    match Type::try_from(value.clone()) {
-                                        Ok(Type::Int(value)) => vec![value],
-                                        Ok(sql_type) => {
-                                            return Err(ErrorEvent::UnimplementedBranch(format!(
-                                                "{:?} is not supported yet",
-                                                sql_type
-                                            )))
-                                        }
-                                        Err(TypeError::Unsupported(message)) => {
-                                            return Err(ErrorEvent::UnimplementedBranch(message))
-                                        }
-                                    }
-                                } else {
-                                    return Err(ErrorEvent::UnimplementedBranch(format!(
-                                        "Non value RHS type {:?} is not supported",
-                                        right
-                                    )));
-                                }
-                            }
-                            None => table.keys().cloned().collect::<Vec<BigInt>>(),
-                            selection => {
-                                return Err(ErrorEvent::UnimplementedBranch(format!(
-                                    "UNIMPLEMENTED HANDLING OF \n{:?}\n WHERE CLAUSE!",
-                                    selection
-                                )))
-                            }
-                        };
-                        let Assignment { value, .. } = &assignments[0];
-                        let value = if let Expr::Value(value) = value {
-                            match Type::try_from(value.clone()) {
-                                Ok(Type::Int(value)) => value,
-                                Ok(sql_type) => {
-                                    return Err(ErrorEvent::UnimplementedBranch(format!(
-                                        "{:?} is not supported yet",
-                                        sql_type
-                                    )))
-                                }
-                                Err(TypeError::Unsupported(message)) => {
-                                    return Err(ErrorEvent::UnimplementedBranch(message))
-                                }
-                            }
-                        } else {
-                            return Err(ErrorEvent::UnimplementedBranch(format!(
-                                "Non value RHS type {:?} is not supported",
-                                value
-                            )));
-                        };
-                        for key in keys {
-                            if let Some(old_value) = table.get_mut(&key) {
-                                *old_value = bincode::serialize(&value).unwrap();
-                            }
-                        }
-                        Ok(EngineEvent::RecordsUpdated)
+            _ => None,
+        }
+    }
+
+    /// the predicate selecting the rows a statement reads along with those rows:
+    /// a row of a primary key is looked up by its key, equalities on columns are selected
+    /// by the storage and any other condition is checked against every row
+    fn target(
+        &self,
+        table_name: &str,
+        scope: &Scope,
+        selection: Option<&Expr>,
+        collation: Collation,
+        operators: &Operators,
+        storage: &dyn Storage,
+    ) -> Result<(Option<Predicate>, KeyedRows), ErrorEvent> {
+        let condition = match selection {
+            None => return Ok((None, self.rows(table_name, storage)?)),
+            Some(condition) => condition,
+        };
+        let predicate = match self.key_lookup(condition, collation) {
+            Some(tuple) => Some(match self.primary_keys.get(&tuple) {
+                Some(key) => Predicate::Equal(Type::Int(key.clone())),
+                None => Predicate::In(vec![]),
+            }),
+            None => self.predicate(condition, scope, collation),
+        };
+        match predicate {
+            Some(predicate) if self.materialized.is_none() => {
+                let rows = stored(table_name, storage, Some(predicate.clone()))?;
+                Ok((Some(predicate), rows))
+            }
+            _ => {
+                let mut rows = vec![];
+                for (key, record) in self.rows(table_name, storage)? {
+                    if satisfies(condition, scope, &record, collation, operators)? {
+                        rows.push((key, record));
                     }
                 }
+                let keys = rows.iter().map(|(key, _)| Type::Int(key.clone())).collect();
+                Ok((Some(Predicate::In(keys)), rows))
             }
-            Some(Statement::Delete {
-                table_name,
-                selection,
-            }) => {
-                let table_name = table_name.to_string();
-                match self.tables.get_mut(&table_name) {
-                    None => Err(ErrorEvent::TableDoesNotExist(table_name.to_string())),
-                    Some(table) => {
-                        let keys = match selection {
-                            Some(Expr::BinaryOp { right, .. }) => {
-                                if let Expr::Value(value) = right.deref() {
-                                    match Type::try_from(value.clone()) {
-                                        Ok(Type::Int(value)) => vec![value],
-                                        Ok(sql_type) => {
-                                            return Err(ErrorEvent::UnimplementedBranch(format!(
-                                                "{:?} is not supported yet",
-                                                sql_type
-                                            )))
-                                        }
-                                        Err(TypeError::Unsupported(message)) => {
-                                            return Err(ErrorEvent::UnimplementedBranch(message))
-                                        }
-                                    }
-                                } else {
-                                    return Err(ErrorEvent::UnimplementedBranch(format!(
-                                        "Non value RHS type {:?} is not supported",
-                                        right
-                                    )));
-                                }
-                            }
-                            None => table.keys().cloned().collect::<Vec<BigInt>>(),
-                            selection => {
-                                return Err(ErrorEvent::UnimplementedBranch(format!(
-                                    "UNIMPLEMENTED HANDLING OF \n{:?}\n WHERE CLAUSE!",
-                                    selection
-                                )))
-                            }
-                        };
-                        for key in keys {
-                            table.remove(&key);
+        }
+    }
+
+    fn column_index(&self, column_name: &str) -> Result<usize, ErrorEvent> {
+        self.columns
+            .iter()
+            .position(|column| column == column_name)
+            .ok_or_else(|| ErrorEvent::ColumnDoesNotExist(column_name.to_owned()))
+    }
+
+    /// name, type, nullable, key and default of every column
+    fn describe(&self) -> Vec<Vec<Type>> {
+        self.columns
+            .iter()
+            .zip(&self.types)
+            .zip(&self.options)
+            .enumerate()
+            .map(|(index, ((name, data_type), options))| {
+                let mut nullable = "YES";
+                let mut key = "";
+                if self.primary_key.contains(&index) {
+                    nullable = "NO";
+                    key = "PRI";
+                }
+                let mut default = "NULL".to_owned();
+                for option in options {
+                    match option {
+                        ColumnOption::NotNull => nullable = "NO",
+                        ColumnOption::Unique { is_primary: true } => {
+                            nullable = "NO";
+                            key = "PRI";
                         }
-                        Ok(EngineEvent::RecordsDeleted)
+                        ColumnOption::Unique { is_primary: false } => key = "UNI",
+                        ColumnOption::Default(expr) => default = expr.to_string(),
+                        _ => {}
                     }
                 }
+                vec![
+                    Type::VarChar(name.clone()),
+                    Type::VarChar(data_type.to_string()),
+                    Type::VarChar(nullable.to_owned()),
+                    Type::VarChar(key.to_owned()),
+                    Type::VarChar(default),
+                ]
+            })
+            .collect()
+    }
+
+    fn scope(&self, qualifier: &str) -> Scope {
+        Scope {
+            columns: self
+                .columns
+                .iter()
+                .map(|column| (qualifier.to_owned(), column.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// rows produced and time spent by every stage of a query
+#[derive(Default)]
+struct Analysis {
+    stages: Vec<(&'static str, usize, Duration)>,
+}
+
+impl Analysis {
+    fn record(&mut self, stage: &'static str, rows: usize, started: Instant) {
+        self.stages.push((stage, rows, started.elapsed()));
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (stage, rows, time) in &self.stages {
+            writeln!(f, "{}: rows={} time={:?}", stage, rows, time)?;
+        }
+        Ok(())
+    }
+}
+
+/// what rows of a query result are ordered by, an output of the select list,
+/// e.g. an alias, or a value of the row
+enum SortKey {
+    Projected(usize),
+    Row(Output),
+}
+
+/// a column of a query result, either taken from the row as is or computed for each row
+enum Output {
+    Column(usize),
+    Computed(Expr),
+}
+
+/// a function of the select list computed over all records of a query result
+enum Aggregate {
+    Count,
+    Sum(Expr),
+    Min(Expr),
+    Max(Expr),
+    /// truncated towards zero to stay an exact integer
+    Avg(Expr),
+    BoolAnd(Expr),
+    BoolOr(Expr),
+}
+
+impl Aggregate {
+    fn of(expr: &Expr) -> Option<Aggregate> {
+        let (name, args) = match expr {
+            Expr::Function(Function { name, args, .. }) => (name.to_string().to_lowercase(), args),
+            _ => return None,
+        };
+        match (name.as_str(), args.as_slice()) {
+            ("count", [Expr::Wildcard]) => Some(Aggregate::Count),
+            ("sum", [arg]) => Some(Aggregate::Sum(arg.clone())),
+            ("min", [arg]) => Some(Aggregate::Min(arg.clone())),
+            ("max", [arg]) => Some(Aggregate::Max(arg.clone())),
+            ("avg", [arg]) => Some(Aggregate::Avg(arg.clone())),
+            ("bool_and", [arg]) => Some(Aggregate::BoolAnd(arg.clone())),
+            ("bool_or", [arg]) => Some(Aggregate::BoolOr(arg.clone())),
+            _ => None,
+        }
+    }
+
+    /// the value over the records, MIN, MAX, AVG, BOOL_AND and BOOL_OR of no values are NULL
+    fn compute(&self, scope: &Scope, records: &[Vec<Type>]) -> Result<Type, ErrorEvent> {
+        let or_null = |value: Option<BigInt>| value.map(Type::Int).unwrap_or(Type::Null);
+        Ok(match self {
+            Aggregate::Count => match i64::try_from(records.len()) {
+                Ok(count) => Type::Int(BigInt::from(count)),
+                Err(_) => {
+                    return Err(ErrorEvent::TypeMismatch(format!(
+                        "COUNT of {} records does not fit into i64",
+                        records.len()
+                    )))
+                }
+            },
+            Aggregate::Sum(arg) => Type::Int(integers(arg, scope, records)?.into_iter().sum()),
+            Aggregate::Min(arg) => or_null(integers(arg, scope, records)?.into_iter().min()),
+            Aggregate::Max(arg) => or_null(integers(arg, scope, records)?.into_iter().max()),
+            Aggregate::Avg(arg) => {
+                let values = integers(arg, scope, records)?;
+                if values.is_empty() {
+                    Type::Null
+                } else {
+                    let count = BigInt::from(values.len());
+                    Type::Int(values.into_iter().sum::<BigInt>() / count)
+                }
             }
-            Some(Statement::Query(query)) => {
-                let Query { body, .. } = &*query;
-                if let SetExpr::Select(select) = &body {
-                    let Select {
-                        selection, from, ..
-                    } = select.deref();
-                    let TableWithJoins { relation, .. } = &from[0];
-                    let table_name = match relation {
-                        TableFactor::Table { name, .. } => name.to_string(),
-                        _ => {
-                            return Err(ErrorEvent::UnimplementedBranch(format!(
-                                "UNIMPLEMENTED SELECTION FROM MULTIPLE TABLES \n{:?}\n",
-                                relation
-                            )))
-                        }
-                    };
-                    match self.tables.get(&table_name) {
-                        None => Err(ErrorEvent::TableDoesNotExist(table_name)),
-                        Some(table) => match selection {
-                            Some(Expr::BinaryOp { left: _, op, right }) => match op {
-                                BinaryOperator::Eq => {
-                                    if let Expr::Value(value) = right.deref() {
-                                        if let Ok(Type::Int(value)) = Type::try_from(value.clone())
-                                        {
-                                            table.get(&value)
-                                                        .ok_or_else(|| ErrorEvent::UnimplementedBranch("UNIMPLEMENTED HANDLING OF NO INSERTED VALUE".to_owned()))
-                                                        .map(|record| EngineEvent::RecordsSelected(vec![record.clone()]))
-                                        } else {
-                                            return Err(
-                                                        ErrorEvent::UnimplementedBranch(
-                                                            format!(
-                                                                "UNIMPLEMENTED HANDLING OF STRING PARSING \n{:?}\n IN WHERE X = RIGHT!",
-                                                                right
-                                                            )
-                                                        )
-                                                    );
-                                        }
-                                    } else {
-                                        return Err(
-                                                    ErrorEvent::UnimplementedBranch(
-                                                        format!("UNIMPLEMENTED HANDLING OF \n{:?}\n IN WHERE X = RIGHT!", right)
-                                                    )
-                                                );
-                                    }
-                                }
-                                operator => {
-                                    return Err(ErrorEvent::UnimplementedBranch(format!(
-                                    "UNIMPLEMENTED HANDLING OF OPERATOR \n{:?}\n IN WHERE CLAUSE",
-                                    operator
-                                )))
-                                }
-                            },
-                            Some(Expr::Between {
-                                negated, low, high, ..
-                            }) => {
-                                if let (Expr::Value(low), Expr::Value(high)) =
-                                    (low.deref(), high.deref())
-                                {
-                                    if let (Ok(Type::Int(low)), Ok(Type::Int(high))) =
-                                        (Type::try_from(low.clone()), Type::try_from(high.clone()))
-                                    {
-                                        if *negated {
-                                            Ok(EngineEvent::RecordsSelected(
-                                                table
-                                                    .range(..low)
-                                                    .chain(table.range(high..).skip(1))
-                                                    .map(|(_key, value)| value)
-                                                    .cloned()
-                                                    .collect(),
-                                            ))
-                                        } else {
-                                            Ok(EngineEvent::RecordsSelected(
-                                                table
-                                                    .range(low..=high)
-                                                    .map(|(_key, value)| value)
-                                                    .cloned()
-                                                    .collect(),
-                                            ))
-                                        }
-                                    } else {
-                                        return Err(
-                                                ErrorEvent::UnimplementedBranch(
-                                                    format!(
-                                                        "UNIMPLEMENTED HANDLING OF STRING PARSING \n IN WHERE BETWEEN {:?} AND {:?}",
-                                                        low, high
-                                                    )
-                                                )
-                                            );
-                                    }
-                                } else {
-                                    return Err(
-                                            ErrorEvent::UnimplementedBranch(
-                                                format!("UNIMPLEMENTED HANDLING OF \n IN WHERE BETWEEN {:?} AND {:?}", low, high)
-                                            )
-                                        );
-                                }
-                            }
-                            Some(Expr::InList { list, negated, .. }) => {
-                                let mut records = vec![];
-                                let mut set = Vec::new();
-                                for item in list {
-                                    if let Expr::Value(value) = item {
-                                        if let Ok(Type::Int(value)) = Type::try_from(value.clone())
-                                        {
-                                            set.push(value)
-                                        } else {
-                                            return Err(
-                                                    ErrorEvent::UnimplementedBranch(
-                                                        format!("UNIMPLEMENTED HANDLING OF STRING PARSING IN WHERE 'IN (x, y, z)' for {:?}", value)
-                                                    )
-                                                );
-                                        }
-                                    } else {
-                                        return Err(
-                                                ErrorEvent::UnimplementedBranch(
-                                                    format!("UNIMPLEMENTED HANDLING OF VALUES PARSING IN WHERE 'IN (x, y, z)' for {:?}", item)
-                                                )
-                                            );
-                                    }
-                                }
-                                for (key, record) in table.iter() {
-                                    if !*negated && set.contains(key) {
-                                        records.push(record.clone())
-                                    }
-                                    if *negated && !set.contains(key) {
-                                        records.push(record.clone())
-                                    }
-                                }
-                                Ok(EngineEvent::RecordsSelected(records))
-                            }
-                            None => {
-                                let copy = table.values().cloned().collect();
-                                Ok(EngineEvent::RecordsSelected(copy))
-                            }
-                            selection => {
-                                return Err(ErrorEvent::UnimplementedBranch(format!(
-                                    "UNIMPLEMENTED HANDLING OF \n{:?}\n WHERE CLAUSE!",
-                                    selection
-                                )))
-                            }
-                        },
-                    }
+            Aggregate::BoolAnd(arg) => {
+                let values = booleans(arg, scope, records)?;
+                if values.is_empty() {
+                    Type::Null
                 } else {
-                    return Err(ErrorEvent::UnimplementedBranch(format!(
-                        "UNIMPLEMENTED HANDLING OF \n{:?}\n SELECT QUERY!",
-                        query
-                    )));
+                    Type::Bool(values.into_iter().all(|value| value))
                 }
             }
-            statement => {
-                return Err(ErrorEvent::UnimplementedBranch(format!(
-                    "UNIMPLEMENTED HANDLING OF \n{:?}\n STATEMENT!",
-                    statement
+            Aggregate::BoolOr(arg) => {
+                let values = booleans(arg, scope, records)?;
+                if values.is_empty() {
+                    Type::Null
+                } else {
+                    Type::Bool(values.into_iter().any(|value| value))
+                }
+            }
+        })
+    }
+}
+
+/// values of the expression for every record, which are all integers, NULLs are left out
+fn integers(arg: &Expr, scope: &Scope, records: &[Vec<Type>]) -> Result<Vec<BigInt>, ErrorEvent> {
+    let mut values = vec![];
+    for record in records {
+        match evaluate(arg, scope, record)? {
+            Type::Int(value) => values.push(value),
+            Type::Null => {}
+            value => {
+                return Err(ErrorEvent::TypeMismatch(format!(
+                    "{:?} is not an integer to aggregate",
+                    value
                 )))
             }
         }
     }
+    Ok(values)
 }
 
-impl Default for Engine {
-    fn default() -> Self {
-        Self {
-            dialect: GenericDialect {},
-            tables: HashMap::new(),
+/// values of the expression for every record, which are all booleans, NULLs are left out
+fn booleans(arg: &Expr, scope: &Scope, records: &[Vec<Type>]) -> Result<Vec<bool>, ErrorEvent> {
+    let mut values = vec![];
+    for record in records {
+        match evaluate(arg, scope, record)? {
+            Type::Bool(value) => values.push(value),
+            Type::Null => {}
+            value => {
+                return Err(ErrorEvent::TypeMismatch(format!(
+                    "{:?} is not a boolean to aggregate",
+                    value
+                )))
+            }
         }
     }
+    Ok(values)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// columns of the rows produced by a FROM clause, qualified by their table name or alias
+#[derive(Default)]
+struct Scope {
+    columns: Vec<(String, String)>,
+}
+
+impl Scope {
+    fn resolve(&self, expr: &Expr) -> Result<usize, ErrorEvent> {
+        let (table, column) = match expr {
+            Expr::Identifier(column) => (None, column),
+            Expr::CompoundIdentifier(names) if names.len() == 2 => (Some(&names[0]), &names[1]),
+            expr => {
+                return Err(ErrorEvent::UnimplementedBranch(format!(
+                    "UNIMPLEMENTED HANDLING OF \n{:?}\n COLUMN REFERENCE",
+                    expr
+                )))
+            }
+        };
+        self.columns
+            .iter()
+            .position(|(qualifier, name)| {
+                name == column && table.map(|table| table == qualifier).unwrap_or(true)
+            })
+            .ok_or_else(|| ErrorEvent::ColumnDoesNotExist(expr.to_string()))
+    }
+
+    fn projection(&self, projection: &[SelectItem]) -> Result<Vec<(String, Output)>, ErrorEvent> {
+        let mut outputs = vec![];
+        for item in projection {
+            match item {
+                SelectItem::Wildcard => outputs.extend(self.outputs(0..self.columns.len())),
+                SelectItem::QualifiedWildcard(table_name) => {
+                    let table_name = table_name.to_string();
+                    let columns = self
+                        .columns
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, (qualifier, _))| qualifier == &table_name)
+                        .map(|(index, _)| index)
+                        .collect::<Vec<usize>>();
+                    if columns.is_empty() {
+                        return Err(ErrorEvent::TableDoesNotExist(table_name));
+                    }
+                    outputs.extend(self.outputs(columns))
+                }
+                SelectItem::UnnamedExpr(expr @ Expr::Identifier(_))
+                | SelectItem::UnnamedExpr(expr @ Expr::CompoundIdentifier(_)) => {
+                    outputs.extend(self.outputs(vec![self.resolve(expr)?]))
+                }
+                SelectItem::UnnamedExpr(expr) => {
+                    outputs.push((expr.to_string(), Output::Computed(expr.clone())))
+                }
+                SelectItem::ExprWithAlias { expr, alias } => {
+                    outputs.push((alias.clone(), Output::Computed(expr.clone())))
+                }
+            }
+        }
+        Ok(outputs)
+    }
+
+    fn outputs<I: IntoIterator<Item = usize>>(&self, indexes: I) -> Vec<(String, Output)> {
+        indexes
+            .into_iter()
+            .map(|index| (self.columns[index].1.clone(), Output::Column(index)))
+            .collect()
+    }
+
+    /// sort keys of ORDER BY terms with whether they are ascending, a name of the select list
+    /// takes precedence over a column with the same name
+    fn sort_keys(
+        &self,
+        order_by: &[OrderByExpr],
+        outputs: &[(String, Output)],
+    ) -> Result<Vec<(SortKey, bool)>, ErrorEvent> {
+        let mut keys = vec![];
+        for OrderByExpr { expr, asc } in order_by {
+            let key = match expr {
+                Expr::Identifier(name) => {
+                    match outputs.iter().position(|(output, _)| output == name) {
+                        Some(index) => SortKey::Projected(index),
+                        None => SortKey::Row(Output::Column(self.resolve(expr)?)),
+                    }
+                }
+                Expr::CompoundIdentifier(_) => SortKey::Row(Output::Column(self.resolve(expr)?)),
+                expr => SortKey::Row(Output::Computed(expr.clone())),
+            };
+            keys.push((key, asc.unwrap_or(true)));
+        }
+        Ok(keys)
+    }
+
+    /// records of every distinct combination of values of the grouping columns
+    fn group(
+        &self,
+        group_by: &[Expr],
+        outputs: &[(String, Output)],
+        records: Vec<Vec<Type>>,
+    ) -> Result<Vec<Vec<Vec<Type>>>, ErrorEvent> {
+        let mut columns = vec![];
+        for expr in group_by {
+            columns.push(self.resolve(expr)?);
+        }
+        for (name, output) in outputs {
+            if let Output::Column(index) = output {
+                if !columns.contains(index) {
+                    return Err(ErrorEvent::ColumnNotGrouped(name.clone()));
+                }
+            }
+        }
+        let mut groups = BTreeMap::new();
+        for record in records {
+            groups
+                .entry(
+                    columns
+                        .iter()
+                        .map(|index| record[*index].clone())
+                        .collect::<Vec<Type>>(),
+                )
+                .or_insert_with(Vec::new)
+                .push(record);
+        }
+        Ok(groups.into_values().collect())
+    }
+
+    /// value of the output for a group of records, columns and expressions
+    /// other than aggregates are taken from its first record
+    fn value(&self, output: &Output, group: &[Vec<Type>]) -> Result<Type, ErrorEvent> {
+        let record = &group[0];
+        match output {
+            Output::Column(index) => Ok(record[*index].clone()),
+            Output::Computed(expr) => match Aggregate::of(expr) {
+                Some(aggregate) => aggregate.compute(self, group),
+                None => evaluate(expr, self, record),
+            },
+        }
+    }
+
+    /// whether the group satisfies the HAVING condition, whose aggregates are computed
+    /// over the group and whose columns are taken from its first record
+    fn having(
+        &self,
+        condition: &Expr,
+        group: &[Vec<Type>],
+        collation: Collation,
+        operators: &Operators,
+    ) -> Result<bool, ErrorEvent> {
+        let record = group.first().map(Vec::as_slice).unwrap_or(&[]);
+        let condition = self.bind_aggregates(condition, group)?;
+        satisfies(&condition, self, record, collation, operators)
+    }
+
+    /// the expression with every aggregate replaced by its value over the group
+    fn bind_aggregates(&self, expr: &Expr, group: &[Vec<Type>]) -> Result<Expr, ErrorEvent> {
+        if let Some(aggregate) = Aggregate::of(expr) {
+            return Ok(Expr::Value(Value::from(aggregate.compute(self, group)?)));
+        }
+        Ok(match expr {
+            Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+                left: Box::new(self.bind_aggregates(left, group)?),
+                op: op.clone(),
+                right: Box::new(self.bind_aggregates(right, group)?),
+            },
+            Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+                op: op.clone(),
+                expr: Box::new(self.bind_aggregates(expr, group)?),
+            },
+            Expr::Nested(expr) => Expr::Nested(Box::new(self.bind_aggregates(expr, group)?)),
+            Expr::IsNull(expr) => Expr::IsNull(Box::new(self.bind_aggregates(expr, group)?)),
+            Expr::IsNotNull(expr) => Expr::IsNotNull(Box::new(self.bind_aggregates(expr, group)?)),
+            expr => expr.clone(),
+        })
+    }
+
+    /// a single record of aggregates, other outputs are not allowed without a grouping
+    fn aggregate(
+        &self,
+        outputs: &[(String, Output)],
+        records: &[Vec<Type>],
+    ) -> Result<Vec<Type>, ErrorEvent> {
+        let mut row = vec![];
+        for (name, output) in outputs {
+            match output {
+                Output::Computed(expr) => match Aggregate::of(expr) {
+                    Some(aggregate) => row.push(aggregate.compute(self, records)?),
+                    None => return Err(ErrorEvent::ColumnNotGrouped(name.clone())),
+                },
+                Output::Column(_) => return Err(ErrorEvent::ColumnNotGrouped(name.clone())),
+            }
+        }
+        Ok(row)
+    }
+
+    fn check_order_by(&self, order_by: &[OrderByExpr]) -> Result<(), ErrorEvent> {
+        for OrderByExpr { expr, .. } in order_by {
+            self.resolve(expr)?;
+        }
+        Ok(())
+    }
+
+    /// columns of both scopes, those of the other one following
+    fn joined(self, other: Scope) -> Scope {
+        let mut columns = self.columns;
+        columns.extend(other.columns);
+        Scope { columns }
+    }
+
+    fn product(
+        self,
+        records: Vec<Vec<Type>>,
+        other: Scope,
+        others: Vec<Vec<Type>>,
+    ) -> (Scope, Vec<Vec<Type>>) {
+        let mut product = vec![];
+        for record in &records {
+            for other in &others {
+                let mut row = record.clone();
+                row.extend(other.iter().cloned());
+                product.push(row);
+            }
+        }
+        (self.joined(other), product)
+    }
+}
+
+impl Engine {
+    /// an engine with the tables of a script of CREATE TABLE statements
+    pub fn from_schema(ddl: &str) -> Result<Engine, ErrorEvent> {
+        let mut engine = Engine::default();
+        let statements = match Parser::parse_sql(&engine.dialect, ddl.to_owned()) {
+            Ok(statements) => statements,
+            Err(error) => return Err(ErrorEvent::ParseError(error.to_string())),
+        };
+        for statement in statements {
+            match statement {
+                Statement::CreateTable {
+                    name,
+                    columns,
+                    constraints,
+                    ..
+                } => {
+                    engine.create_table(name.to_string(), columns, constraints)?;
+                }
+                statement => return Err(unsupported(Some(statement))),
+            }
+        }
+        Ok(engine)
+    }
+
+    pub fn set_collation(&mut self, collation: Collation) {
+        self.collation = collation;
+        if let Some(cache) = self.cache.as_mut() {
+            cache.entries.clear();
+        }
+    }
+
+    /// name and type of every column of the table
+    pub fn column_types(&self, table_name: &str) -> Result<Vec<(String, TypeTag)>, ErrorEvent> {
+        match self.tables.get(table_name) {
+            None => Err(ErrorEvent::TableDoesNotExist(table_name.to_owned())),
+            Some(table) => table
+                .columns
+                .iter()
+                .zip(&table.types)
+                .map(|(name, data_type)| Ok((name.clone(), tag_of(data_type)?)))
+                .collect(),
+        }
+    }
+
+    /// number of records in the table
+    pub fn row_count(&self, table_name: &str) -> Result<usize, ErrorEvent> {
+        match self.tables.get(table_name) {
+            None => Err(ErrorEvent::TableDoesNotExist(table_name.to_owned())),
+            Some(table) => Ok(table.rows(table_name, self.storage.as_ref())?.len()),
+        }
+    }
+
+    /// the CREATE TABLE and INSERT statements that recreate the table with its records
+    pub fn dump_table(&self, table_name: &str) -> Result<String, ErrorEvent> {
+        let table = match self.tables.get(table_name) {
+            None => return Err(ErrorEvent::TableDoesNotExist(table_name.to_owned())),
+            Some(table) => table,
+        };
+        let mut columns = table
+            .columns
+            .iter()
+            .zip(&table.types)
+            .zip(&table.options)
+            .map(|((name, data_type), options)| {
+                options
+                    .iter()
+                    .fold(format!("{} {}", name, data_type), |definition, option| {
+                        format!("{} {}", definition, option)
+                    })
+            })
+            .collect::<Vec<String>>();
+        // a key of a single column may have been declared along with the column
+        let declared = table.primary_key.iter().all(|index| {
+            table.options[*index].contains(&ColumnOption::Unique { is_primary: true })
+        });
+        if !declared {
+            let names = table
+                .primary_key
+                .iter()
+                .map(|index| table.columns[*index].clone())
+                .collect::<Vec<String>>();
+            columns.push(format!("PRIMARY KEY ({})", names.join(", ")));
+        }
+        let mut dump = format!("CREATE TABLE {} ({});\n", table_name, columns.join(", "));
+        for (_, record) in table.rows(table_name, self.storage.as_ref())? {
+            let values = record
+                .iter()
+                .map(|value| Value::from(value.clone()).to_string())
+                .collect::<Vec<String>>();
+            dump.push_str(&format!(
+                "INSERT INTO {} VALUES ({});\n",
+                table_name,
+                values.join(", ")
+            ));
+        }
+        Ok(dump)
+    }
+
+    /// keeps results of up to `capacity` SELECT queries until a table they read from is written
+    pub fn enable_query_cache(&mut self, capacity: usize) {
+        self.cache = Some(QueryCache {
+            capacity,
+            entries: VecDeque::new(),
+        });
+    }
+
+    /// aborts a query once its joins combine more than `limit` rows
+    pub fn set_max_scanned_rows(&mut self, limit: usize) {
+        self.max_scanned_rows = Some(limit);
+    }
+
+    /// rejects CREATE INDEX on a table that has `limit` indexes already, as every index
+    /// keeps the keys of all rows in memory
+    pub fn set_max_indexes(&mut self, limit: usize) {
+        self.max_indexes = Some(limit);
+    }
+
+    /// rejects WHERE clauses nested deeper than `depth`, as they are evaluated recursively
+    pub fn set_max_expression_depth(&mut self, depth: usize) {
+        self.max_expression_depth = depth;
+    }
+
+    /// registers an operator, as sqlparser knows only the built-in ones
+    /// it is written as a function of two arguments, e.g. `WHERE near(location, 'home')`
+    pub fn register_operator<O>(&mut self, name: &str, operator: O)
+    where
+        O: Fn(&Type, &Type) -> bool + 'static,
+    {
+        self.operators
+            .insert(name.to_lowercase(), Box::new(operator));
+        if let Some(cache) = self.cache.as_mut() {
+            cache.entries.clear();
+        }
+    }
+
+    /// registers a callback fired for every row written to the table,
+    /// an error returned from a `When::Before` callback aborts the statement
+    pub fn on_write<H>(&mut self, table_name: &str, when: When, hook: H)
+    where
+        H: FnMut(&WriteEvent) -> Result<(), ErrorEvent> + 'static,
+    {
+        self.hooks
+            .entry((table_name.to_owned(), when))
+            .or_default()
+            .push(Box::new(hook));
+    }
+
+    /// executes the query and reports rows and time of every stage
+    pub fn explain_analyze(&mut self, sql: String) -> ExecutionResult {
+        let mut statements = match Parser::parse_sql(&self.dialect, sql) {
+            Ok(ok) => ok,
+            Err(error) => return Err(ErrorEvent::ParseError(error.to_string())),
+        };
+        match statements.pop() {
+            Some(Statement::Query(query)) => {
+                let mut analysis = Analysis::default();
+                self.query(&query, &mut analysis)?;
+                Ok(EngineEvent::QueryPlan(analysis.to_string()))
+            }
+            statement => Err(unsupported(statement)),
+        }
+    }
+
+    /// reports how many records an UPDATE or DELETE would write without running it
+    pub fn explain(&self, sql: String) -> ExecutionResult {
+        let mut statements = match Parser::parse_sql(&self.dialect, sql) {
+            Ok(ok) => ok,
+            Err(error) => return Err(ErrorEvent::ParseError(error.to_string())),
+        };
+        let (stage, table_name, selection) = match statements.pop() {
+            Some(Statement::Update {
+                table_name,
+                selection,
+                ..
+            }) => ("update", table_name.to_string(), selection),
+            Some(Statement::Delete {
+                table_name,
+                selection,
+            }) => ("delete", table_name.to_string(), selection),
+            statement => return Err(unsupported(statement)),
+        };
+        match self.tables.get(&table_name) {
+            None => Err(ErrorEvent::TableDoesNotExist(table_name)),
+            Some(table) => {
+                let (_, rows) = table.target(
+                    &table_name,
+                    &table.scope(&table_name),
+                    selection.as_ref(),
+                    self.collation,
+                    &self.operators,
+                    self.storage.as_ref(),
+                )?;
+                Ok(EngineEvent::QueryPlan(format!(
+                    "{} {}: rows={}\n",
+                    stage,
+                    table_name,
+                    rows.len()
+                )))
+            }
+        }
+    }
+
+    /// runs the query and gives its records with typed accessors
+    pub fn select_rows(&mut self, sql: String) -> Result<Rows, ErrorEvent> {
+        let mut statements = match Parser::parse_sql(&self.dialect, sql) {
+            Ok(ok) => ok,
+            Err(error) => return Err(ErrorEvent::ParseError(error.to_string())),
+        };
+        match statements.pop() {
+            Some(Statement::Query(query)) => {
+                let (columns, records) = self.query(&query, &mut Analysis::default())?;
+                let columns: Arc<[String]> = Arc::from(columns);
+                Ok(Rows {
+                    records: Row::all(columns.clone(), records),
+                    columns,
+                })
+            }
+            statement => Err(unsupported(statement)),
+        }
+    }
+
+    #[allow(clippy::cognitive_complexity)] // TODO simplify SQL execution
+    pub fn execute(&mut self, sql: String) -> ExecutionResult {
+        // sqlparser does not know EXPLAIN yet
+        let trimmed = sql.trim_start();
+        if let Some(query) = strip_keywords(trimmed, "EXPLAIN ANALYZE ") {
+            return self.explain_analyze(query.to_owned());
+        }
+        if let Some(statement) = strip_keywords(trimmed, "EXPLAIN ") {
+            return self.explain(statement.to_owned());
+        }
+        if let Some(definition) = strip_keywords(trimmed, "CREATE TABLE IF NOT EXISTS ") {
+            return self.create_table_if_not_exists(format!("CREATE TABLE {}", definition));
+        }
+        // nor CREATE INDEX, which is written as CREATE INDEX name ON table (column)
+        if let Some(definition) = strip_keywords(trimmed, "CREATE INDEX ") {
+            return self.create_index(definition);
+        }
+        if let Some(parameter) = table_parameter(&sql) {
+            return Err(ErrorEvent::InvalidParameterPosition(parameter));
+        }
+        let mut statements = match Parser::parse_sql(&self.dialect, sql) {
+            Ok(ok) => ok,
+            Err(error) => return Err(ErrorEvent::ParseError(error.to_string())),
+        };
+        let selection = match statements.last() {
+            Some(Statement::Query(query)) => match &query.body {
+                SetExpr::Select(select) => select.selection.as_ref(),
+                _ => None,
+            },
+            Some(Statement::Update { selection, .. })
+            | Some(Statement::Delete { selection, .. }) => selection.as_ref(),
+            _ => None,
+        };
+        if let Some(selection) = selection {
+            if nested_deeper(selection, self.max_expression_depth) {
+                return Err(ErrorEvent::ExpressionTooComplex(self.max_expression_depth));
+            }
+        }
+        // evicted up front as a failed statement may have written some rows already
+        match (statements.last(), self.cache.as_mut()) {
+            (Some(Statement::Insert { table_name, .. }), Some(cache))
+            | (Some(Statement::Update { table_name, .. }), Some(cache))
+            | (Some(Statement::Delete { table_name, .. }), Some(cache)) => {
+                cache.invalidate(&table_name.to_string())
+            }
+            _ => {}
+        }
+        match statements.pop() {
+            Some(Statement::CreateTable {
+                name,
+                columns,
+                constraints,
+                ..
+            }) => self.create_table(name.to_string(), columns, constraints),
+            Some(Statement::Insert {
+                table_name,
+                columns,
+                source,
+            }) => {
+                let table_name = table_name.to_string();
+                if !self.tables.contains_key(&table_name) {
+                    return Err(ErrorEvent::TableDoesNotExist(table_name));
+                }
+                let (rows, bulk) = match source.deref() {
+                    Query {
+                        ctes,
+                        body: SetExpr::Values(values),
+                        ..
+                    } if ctes.is_empty() => (
+                        // tuples are evaluated one by one, so the ones before a failing tuple are kept
+                        values
+                            .0
+                            .iter()
+                            .map(|tuple| {
+                                tuple
+                                    .iter()
+                                    .map(|item| evaluate(item, &Scope::default(), &[]))
+                                    .collect::<Result<Vec<Type>, ErrorEvent>>()
+                            })
+                            .collect::<Vec<_>>(),
+                        values.0.len() > 1,
+                    ),
+                    // e.g. INSERT INTO t SELECT ... or INSERT INTO t WITH ... SELECT ...
+                    query => (
+                        self.query(query, &mut Analysis::default())?
+                            .1
+                            .into_iter()
+                            .map(Ok)
+                            .collect(),
+                        false,
+                    ),
+                };
+                match self.tables.get_mut(&table_name) {
+                    None => Err(ErrorEvent::TableDoesNotExist(table_name)),
+                    Some(table) => {
+                        let inserted = rows.len();
+                        for (index, row) in rows.into_iter().enumerate() {
+                            let written = match row {
+                                Ok(row) => table.insert(
+                                    &table_name,
+                                    &columns,
+                                    row,
+                                    &mut self.hooks,
+                                    self.storage.as_mut(),
+                                ),
+                                Err(error) => Err(error),
+                            };
+                            written.map_err(
+                                |error| if bulk { in_tuple(index, error) } else { error },
+                            )?;
+                        }
+                        Ok(EngineEvent::RecordsInserted(inserted))
+                    }
+                }
+            }
+            Some(Statement::Update {
+                table_name,
+                assignments,
+                selection,
+            }) => {
+                let table_name = table_name.to_string();
+                // the storage reports a table it does not hold while its rows are read
+                let mut missing = Table::default();
+                let table = self.tables.get_mut(&table_name).unwrap_or(&mut missing);
+                let scope = table.scope(&table_name);
+                let (predicate, rows) = table.target(
+                    &table_name,
+                    &scope,
+                    selection.as_ref(),
+                    self.collation,
+                    &self.operators,
+                    self.storage.as_ref(),
+                )?;
+                let mut indexes = vec![];
+                for Assignment { id, .. } in &assignments {
+                    indexes.push(table.column_index(id)?);
+                }
+                // every assignment sees the row as it was before the update
+                let mut updates = vec![];
+                for (key, record) in rows {
+                    let mut updated = record.clone();
+                    for (index, Assignment { value, .. }) in indexes.iter().zip(&assignments) {
+                        updated[*index] =
+                            coerce(evaluate(value, &scope, &record)?, table.types.get(*index))?;
+                    }
+                    updates.push((
+                        key,
+                        WriteEvent::Update {
+                            old: record,
+                            new: updated,
+                        },
+                    ));
+                }
+                table.check_updates(&table_name, &updates, self.storage.as_ref())?;
+                let events = updates.iter().map(|(_, event)| event).collect::<Vec<_>>();
+                fire(&mut self.hooks, &table_name, When::Before, &events)?;
+                // the only row of a primary key is overwritten in place, values that do not
+                // depend on the row are set by the storage in the rows of the predicate,
+                // pinned to their keys once an assignment may change which rows
+                // the predicate selects; other values are written row by row
+                let replaced = match (&predicate, updates.as_slice()) {
+                    (Some(Predicate::Equal(key)), [(_, WriteEvent::Update { new, .. })]) => {
+                        Some((key.clone(), new.clone()))
+                    }
+                    _ => None,
+                };
+                let constants = indexes
+                    .iter()
+                    .zip(&assignments)
+                    .map(|(index, Assignment { value, .. })| {
+                        Ok((
+                            table.columns[*index].clone(),
+                            coerce(
+                                evaluate(value, &Scope::default(), &[])?,
+                                table.types.get(*index),
+                            )?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, ErrorEvent>>();
+                match (replaced, constants) {
+                    (Some((key, new)), _) => {
+                        let mut value = vec![key.clone()];
+                        value.extend(new);
+                        self.storage
+                            .replace_value(&table_name, &key, value)
+                            .map_err(|()| {
+                                ErrorEvent::StorageError(format!(
+                                    "row of {} can't be written",
+                                    table_name
+                                ))
+                            })?;
+                    }
+                    (None, Ok(constants)) => {
+                        let predicate = if constants.len() == 1 {
+                            predicate
+                        } else {
+                            Some(Predicate::In(
+                                updates
+                                    .iter()
+                                    .map(|(key, _)| Type::Int(key.clone()))
+                                    .collect(),
+                            ))
+                        };
+                        self.storage.begin();
+                        for assignment in constants {
+                            if let Err(error) =
+                                self.storage
+                                    .update(&table_name, assignment, predicate.clone())
+                            {
+                                self.storage.rollback();
+                                return Err(storage_error(&table_name, error));
+                            }
+                        }
+                        self.storage
+                            .commit()
+                            .map_err(|error| storage_error(&table_name, error))?;
+                    }
+                    (None, Err(_)) => {
+                        let ops = updates
+                            .iter()
+                            .filter_map(|(key, event)| match event {
+                                WriteEvent::Update { new, .. } => Some(Op::Update {
+                                    table_name: table_name.clone(),
+                                    key: Type::Int(key.clone()),
+                                    values: table
+                                        .columns
+                                        .iter()
+                                        .cloned()
+                                        .zip(new.iter().cloned())
+                                        .collect(),
+                                }),
+                                _ => None,
+                            })
+                            .collect();
+                        self.storage.apply_batch(ops).map_err(|()| {
+                            ErrorEvent::StorageError(format!(
+                                "rows of {} can't be written",
+                                table_name
+                            ))
+                        })?;
+                    }
+                }
+                table.reindex(&table_name, self.storage.as_ref())?;
+                fire(&mut self.hooks, &table_name, When::After, &events)?;
+                Ok(EngineEvent::RecordsUpdated(updates.len()))
+            }
+            Some(Statement::Delete {
+                table_name,
+                selection,
+            }) => {
+                let table_name = table_name.to_string();
+                // the storage reports a table it does not hold while its rows are read
+                let mut missing = Table::default();
+                let table = self.tables.get_mut(&table_name).unwrap_or(&mut missing);
+                let (predicate, rows) = table.target(
+                    &table_name,
+                    &table.scope(&table_name),
+                    selection.as_ref(),
+                    self.collation,
+                    &self.operators,
+                    self.storage.as_ref(),
+                )?;
+                let deletes = rows
+                    .into_iter()
+                    .map(|(key, record)| (key, WriteEvent::Delete(record)))
+                    .collect::<Vec<_>>();
+                let events = deletes.iter().map(|(_, event)| event).collect::<Vec<_>>();
+                fire(&mut self.hooks, &table_name, When::Before, &events)?;
+                self.storage
+                    .delete(&table_name, predicate)
+                    .map_err(|error| storage_error(&table_name, error))?;
+                table.reindex(&table_name, self.storage.as_ref())?;
+                fire(&mut self.hooks, &table_name, When::After, &events)?;
+                Ok(EngineEvent::RecordsDeleted(deletes.len()))
+            }
+            Some(Statement::Query(query)) => {
+                let key = query.to_string();
+                if let Some(records) = self.cache.as_mut().and_then(|cache| cache.get(&key)) {
+                    return Ok(EngineEvent::RecordsSelected(records));
+                }
+                let (columns, records) = self.query(&query, &mut Analysis::default())?;
+                let records = Row::all(Arc::from(columns), records);
+                if let Some(cache) = self.cache.as_mut() {
+                    cache.put(key, *query, records.clone());
+                }
+                Ok(EngineEvent::RecordsSelected(records))
+            }
+            Some(Statement::StartTransaction { .. }) => {
+                if self.transaction.is_some() {
+                    return Err(ErrorEvent::TransactionState(
+                        "a transaction is already in progress".to_owned(),
+                    ));
+                }
+                self.transaction = Some(self.tables.clone());
+                self.storage.begin();
+                Ok(EngineEvent::TransactionStarted)
+            }
+            Some(Statement::Commit { .. }) => match self.transaction.take() {
+                None => Err(ErrorEvent::TransactionState(
+                    "there is no transaction in progress".to_owned(),
+                )),
+                // a transaction that can't be committed is kept open to be rolled back
+                Some(tables) => match self.storage.commit() {
+                    Ok(()) => Ok(EngineEvent::TransactionCommitted),
+                    Err(error) => {
+                        self.transaction = Some(tables);
+                        Err(ErrorEvent::StorageError(format!(
+                            "transaction can't be committed: {:?}",
+                            error
+                        )))
+                    }
+                },
+            },
+            Some(Statement::Rollback { .. }) => match self.transaction.take() {
+                None => Err(ErrorEvent::TransactionState(
+                    "there is no transaction in progress".to_owned(),
+                )),
+                Some(tables) => {
+                    self.tables = tables;
+                    self.storage.rollback();
+                    if let Some(cache) = self.cache.as_mut() {
+                        cache.entries.clear();
+                    }
+                    Ok(EngineEvent::TransactionRolledBack)
+                }
+            },
+            Some(Statement::ShowColumns {
+                table_name,
+                filter: None,
+                ..
+            }) => {
+                let table_name = table_name.to_string();
+                match self.tables.get(&table_name) {
+                    None => Err(ErrorEvent::TableDoesNotExist(table_name)),
+                    Some(table) => Ok(EngineEvent::RecordsSelected(Row::all(
+                        Arc::from(
+                            ["Field", "Type", "Null", "Key", "Default"]
+                                .iter()
+                                .map(|column| (*column).to_owned())
+                                .collect::<Vec<String>>(),
+                        ),
+                        table.describe(),
+                    ))),
+                }
+            }
+            statement => Err(unsupported(statement)),
+        }
+    }
+
+    fn create_table(
+        &mut self,
+        table_name: String,
+        columns: Vec<ColumnDef>,
+        constraints: Vec<TableConstraint>,
+    ) -> ExecutionResult {
+        if self.tables.contains_key(&table_name) {
+            Err(ErrorEvent::TableAlreadyExists(table_name))
+        } else {
+            let mut names = vec![];
+            let mut types = vec![];
+            let mut options = vec![];
+            let mut primary_key = vec![];
+            for ColumnDef {
+                name,
+                data_type,
+                options: column_options,
+                ..
+            } in columns
+            {
+                // `id INT PRIMARY KEY` is the same as a PRIMARY KEY (id) constraint
+                if column_options.iter().any(|ColumnOptionDef { option, .. }| {
+                    *option == ColumnOption::Unique { is_primary: true }
+                }) {
+                    primary_key.push(names.len());
+                }
+                names.push(name);
+                types.push(data_type);
+                options.push(
+                    column_options
+                        .into_iter()
+                        .map(|ColumnOptionDef { option, .. }| option)
+                        .collect(),
+                );
+            }
+            for constraint in constraints {
+                if let TableConstraint::Unique {
+                    columns,
+                    is_primary: true,
+                    ..
+                } = constraint
+                {
+                    for column in columns {
+                        match names.iter().position(|name| *name == column) {
+                            Some(index) => primary_key.push(index),
+                            None => return Err(ErrorEvent::ColumnDoesNotExist(column)),
+                        }
+                    }
+                }
+            }
+            let mut definition = vec![(
+                KEY_COLUMN.to_owned(),
+                StorageType::BigInt,
+                [Constraint::PrimaryKey].iter().cloned().collect(),
+            )];
+            for (name, data_type) in names.iter().zip(&types) {
+                definition.push((
+                    name.clone(),
+                    storage_type_of(data_type)?,
+                    Default::default(),
+                ));
+            }
+            self.storage
+                .create_table(&table_name, definition)
+                .map_err(|error| storage_error(&table_name, error))?;
+            self.tables.insert(
+                table_name.clone(),
+                Table {
+                    columns: names,
+                    types,
+                    options,
+                    materialized: None,
+                    next_key: BigInt::from(0),
+                    primary_key,
+                    primary_keys: BTreeMap::new(),
+                    indexes: vec![],
+                },
+            );
+            Ok(EngineEvent::TableCreated(table_name))
+        }
+    }
+
+    /// indexes the column of `name ON table_name (column)`,
+    /// a column that is indexed already is kept as it is
+    fn create_index(&mut self, definition: &str) -> ExecutionResult {
+        let malformed = || {
+            ErrorEvent::ParseError(format!(
+                "expected CREATE INDEX name ON table (column), found CREATE INDEX {}",
+                definition
+            ))
+        };
+        let definition = definition.trim().trim_end_matches(';').trim_end();
+        let name_end = definition.find(char::is_whitespace).ok_or_else(malformed)?;
+        let target =
+            strip_keywords(definition[name_end..].trim_start(), "ON ").ok_or_else(malformed)?;
+        let open = target.find('(').ok_or_else(malformed)?;
+        let table_name = target[..open].trim();
+        let column_name = target[open + 1..]
+            .strip_suffix(')')
+            .ok_or_else(malformed)?
+            .trim();
+        let table = match self.tables.get_mut(table_name) {
+            None => return Err(ErrorEvent::TableDoesNotExist(table_name.to_owned())),
+            Some(table) => table,
+        };
+        let index = table.column_index(column_name)?;
+        if table.indexes.contains(&index) {
+            return Ok(EngineEvent::IndexCreated(table_name.to_owned()));
+        }
+        if let Some(limit) = self.max_indexes {
+            if table.indexes.len() >= limit {
+                return Err(ErrorEvent::IndexLimitExceeded(limit));
+            }
+        }
+        self.storage
+            .create_index(table_name, column_name)
+            .map_err(|error| storage_error(table_name, error))?;
+        table.indexes.push(index);
+        Ok(EngineEvent::IndexCreated(table_name.to_owned()))
+    }
+
+    /// creates the table unless there is one with the same columns already,
+    /// a table with other columns is a conflict rather than silently kept
+    fn create_table_if_not_exists(&mut self, sql: String) -> ExecutionResult {
+        let mut statements = match Parser::parse_sql(&self.dialect, sql) {
+            Ok(ok) => ok,
+            Err(error) => return Err(ErrorEvent::ParseError(error.to_string())),
+        };
+        let (table_name, columns, constraints) = match statements.pop() {
+            Some(Statement::CreateTable {
+                name,
+                columns,
+                constraints,
+                ..
+            }) => (name.to_string(), columns, constraints),
+            statement => return Err(unsupported(statement)),
+        };
+        let table = match self.tables.get(&table_name) {
+            None => return self.create_table(table_name, columns, constraints),
+            Some(table) => table,
+        };
+        let existing = table
+            .columns
+            .iter()
+            .zip(&table.types)
+            .map(|(name, data_type)| format!("{} {}", name, data_type))
+            .collect::<Vec<String>>();
+        let given = columns
+            .iter()
+            .map(
+                |ColumnDef {
+                     name, data_type, ..
+                 }| format!("{} {}", name, data_type),
+            )
+            .collect::<Vec<String>>();
+        if existing == given {
+            Ok(EngineEvent::TableCreated(table_name))
+        } else {
+            Err(ErrorEvent::SchemaConflict(format!(
+                "{} has columns ({}) but ({}) were given",
+                table_name,
+                existing.join(", "),
+                given.join(", ")
+            )))
+        }
+    }
+
+    fn query(
+        &mut self,
+        query: &Query,
+        analysis: &mut Analysis,
+    ) -> Result<(Vec<String>, Vec<Vec<Type>>), ErrorEvent> {
+        let Query {
+            ctes,
+            body,
+            order_by,
+            limit,
+            offset,
+            ..
+        } = query;
+        let (columns, records) = match ctes.as_slice() {
+            [] => self.select(body, order_by, analysis),
+            [Cte { alias, query }] => {
+                let name = alias.name.clone();
+                if query_references(query, &name) {
+                    return Err(ErrorEvent::UnimplementedBranch(format!(
+                        "UNIMPLEMENTED HANDLING OF RECURSIVE CTE \n{:?}\n",
+                        alias
+                    )));
+                }
+                let (columns, records) = self.query(query, analysis)?;
+                let table = Table {
+                    columns: if alias.columns.is_empty() {
+                        columns
+                    } else {
+                        alias.columns.clone()
+                    },
+                    types: vec![],
+                    options: vec![],
+                    materialized: Some(records),
+                    next_key: BigInt::from(0),
+                    primary_key: vec![],
+                    primary_keys: BTreeMap::new(),
+                    indexes: vec![],
+                };
+                // the CTE shadows a table with the same name only for the outer query
+                let shadowed = self.tables.insert(name.clone(), table);
+                let result = self.select(body, order_by, analysis);
+                match shadowed {
+                    Some(shadowed) => self.tables.insert(name, shadowed),
+                    None => self.tables.remove(&name),
+                };
+                result
+            }
+            ctes => Err(ErrorEvent::UnimplementedBranch(format!(
+                "UNIMPLEMENTED HANDLING OF MULTIPLE CTES \n{:?}\n",
+                ctes
+            ))),
+        }?;
+        let skipped = match offset {
+            Some(offset) => row_count(offset)?,
+            None => 0,
+        };
+        let taken = match limit {
+            Some(limit) => row_count(limit)?,
+            None => usize::MAX,
+        };
+        Ok((
+            columns,
+            records.into_iter().skip(skipped).take(taken).collect(),
+        ))
+    }
+
+    fn select(
+        &self,
+        body: &SetExpr,
+        order_by: &[OrderByExpr],
+        analysis: &mut Analysis,
+    ) -> Result<(Vec<String>, Vec<Vec<Type>>), ErrorEvent> {
+        if let SetExpr::Values(Values(values)) = body {
+            let started = Instant::now();
+            let mut records = vec![];
+            for row in values {
+                let mut record = vec![];
+                for item in row {
+                    record.push(evaluate(item, &Scope::default(), &[])?);
+                }
+                records.push(record);
+            }
+            let width = values.first().map(Vec::len).unwrap_or(0);
+            let scope = Scope {
+                columns: (1..=width)
+                    .map(|index| (String::new(), format!("column{}", index)))
+                    .collect(),
+            };
+            scope.check_order_by(order_by)?;
+            analysis.record("values", records.len(), started);
+            Ok((
+                scope.columns.into_iter().map(|(_, name)| name).collect(),
+                records,
+            ))
+        } else if let SetExpr::Select(select) = body {
+            let Select {
+                distinct,
+                projection,
+                selection,
+                from,
+                group_by,
+                having,
+                ..
+            } = select.deref();
+            let started = Instant::now();
+            let (scope, records) = if from.len() == 1 && from[0].joins.is_empty() {
+                let TableWithJoins { relation, .. } = &from[0];
+                let (table_name, qualifier) = match relation {
+                    TableFactor::Table { name, alias, .. } => (
+                        name.to_string(),
+                        alias
+                            .as_ref()
+                            .map(|alias| alias.name.clone())
+                            .unwrap_or_else(|| name.to_string()),
+                    ),
+                    _ => {
+                        return Err(ErrorEvent::UnimplementedBranch(format!(
+                            "UNIMPLEMENTED SELECTION FROM MULTIPLE TABLES \n{:?}\n",
+                            relation
+                        )))
+                    }
+                };
+                let table = match self.tables.get(&table_name) {
+                    None => return Err(ErrorEvent::TableDoesNotExist(table_name)),
+                    Some(table) => table,
+                };
+                let records: Vec<Vec<Type>> = match selection {
+                    Some(condition) if has_subquery(condition) => self.correlated_filter(
+                        table
+                            .rows(&table_name, self.storage.as_ref())?
+                            .into_iter()
+                            .map(|(_, record)| record)
+                            .collect(),
+                        condition,
+                        &table.scope(&qualifier),
+                    )?,
+                    selection => table
+                        .target(
+                            &table_name,
+                            &table.scope(&qualifier),
+                            selection.as_ref(),
+                            self.collation,
+                            &self.operators,
+                            self.storage.as_ref(),
+                        )?
+                        .1
+                        .into_iter()
+                        .map(|(_, record)| record)
+                        .collect(),
+                };
+                analysis.record("scan", records.len(), started);
+                (table.scope(&qualifier), records)
+            } else {
+                self.join(from, selection, analysis)?
+            };
+            let outputs = scope.projection(projection)?;
+            let aggregated = outputs.iter().any(|(_, output)| match output {
+                Output::Computed(expr) => Aggregate::of(expr).is_some(),
+                Output::Column(_) => false,
+            });
+            if aggregated && group_by.is_empty() {
+                let started = Instant::now();
+                let row = scope.aggregate(&outputs, &records)?;
+                let rows = match having {
+                    Some(condition)
+                        if !scope.having(
+                            condition,
+                            &records,
+                            self.collation,
+                            &self.operators,
+                        )? =>
+                    {
+                        vec![]
+                    }
+                    _ => vec![row],
+                };
+                analysis.record("aggregate", rows.len(), started);
+                return Ok((outputs.into_iter().map(|(name, _)| name).collect(), rows));
+            }
+            // every row of the result with the records it stands for
+            let mut groups = if group_by.is_empty() {
+                records.into_iter().map(|record| vec![record]).collect()
+            } else {
+                let started = Instant::now();
+                let groups = scope.group(group_by, &outputs, records)?;
+                analysis.record("group", groups.len(), started);
+                groups
+            };
+            if let Some(condition) = having {
+                let mut kept = vec![];
+                for group in groups {
+                    if scope.having(condition, &group, self.collation, &self.operators)? {
+                        kept.push(group);
+                    }
+                }
+                groups = kept;
+            }
+            let started = Instant::now();
+            let keys = scope.sort_keys(order_by, &outputs)?;
+            let mut projected = vec![];
+            for group in groups {
+                let mut row = vec![];
+                for (_, output) in &outputs {
+                    row.push(scope.value(output, &group)?);
+                }
+                let mut sort_values = vec![];
+                for (key, _) in &keys {
+                    sort_values.push(match key {
+                        SortKey::Projected(index) => row[*index].clone(),
+                        SortKey::Row(output) => scope.value(output, &group)?,
+                    });
+                }
+                // equal sort values are ordered by the first column
+                let key = group[0].first().cloned();
+                projected.push((sort_values, key, row));
+            }
+            analysis.record("projection", projected.len(), started);
+            if !keys.is_empty() {
+                let started = Instant::now();
+                // rows with equal sort values fall back to the order of their first column
+                projected.sort_by(|(left, left_key, _), (right, right_key, _)| {
+                    keys.iter()
+                        .zip(left.iter().zip(right.iter()))
+                        .map(|((_, ascending), (left, right))| {
+                            let ordering = self.collation.compare(left, right);
+                            if *ascending {
+                                ordering
+                            } else {
+                                ordering.reverse()
+                            }
+                        })
+                        .find(|ordering| *ordering != Ordering::Equal)
+                        .unwrap_or_else(|| left_key.cmp(right_key))
+                });
+                analysis.record("sort", projected.len(), started);
+            }
+            let mut rows = projected
+                .into_iter()
+                .map(|(_, _, row)| row)
+                .collect::<Vec<_>>();
+            if *distinct {
+                // the first of equal rows is kept, so they stay in the order they were selected
+                let mut seen = BTreeSet::new();
+                rows.retain(|row| seen.insert(row.clone()));
+            }
+            Ok((outputs.into_iter().map(|(name, _)| name).collect(), rows))
+        } else {
+            Err(ErrorEvent::UnimplementedBranch(format!(
+                "UNIMPLEMENTED HANDLING OF \n{:?}\n SELECT QUERY!",
+                body
+            )))
+        }
+    }
+
+    fn join(
+        &self,
+        from: &[TableWithJoins],
+        selection: &Option<Expr>,
+        analysis: &mut Analysis,
+    ) -> Result<(Scope, Vec<Vec<Type>>), ErrorEvent> {
+        let started = Instant::now();
+        let (mut scope, mut records) = (Scope::default(), vec![vec![]]);
+        let mut scanned = 0;
+        for TableWithJoins { relation, joins } in from {
+            let (other, others) = self.relation(relation)?;
+            scanned = self.scan(scanned, records.len(), others.len())?;
+            let (product_scope, product) = scope.product(records, other, others);
+            scope = product_scope;
+            records = product;
+            for Join {
+                relation,
+                join_operator,
+            } in joins
+            {
+                let (other, others) = self.relation(relation)?;
+                scanned = self.scan(scanned, records.len(), others.len())?;
+                let preserved = match join_operator {
+                    JoinOperator::LeftOuter(JoinConstraint::On(_)) => (true, false),
+                    JoinOperator::RightOuter(JoinConstraint::On(_)) => (false, true),
+                    JoinOperator::FullOuter(JoinConstraint::On(_)) => (true, true),
+                    _ => (false, false),
+                };
+                match join_operator {
+                    JoinOperator::Inner(JoinConstraint::On(condition)) => {
+                        let (product_scope, product) = scope.product(records, other, others);
+                        scope = product_scope;
+                        records =
+                            filter(product, condition, &scope, self.collation, &self.operators)?
+                    }
+                    JoinOperator::CrossJoin => {
+                        let (product_scope, product) = scope.product(records, other, others);
+                        scope = product_scope;
+                        records = product;
+                    }
+                    JoinOperator::LeftOuter(JoinConstraint::On(condition))
+                    | JoinOperator::RightOuter(JoinConstraint::On(condition))
+                    | JoinOperator::FullOuter(JoinConstraint::On(condition)) => {
+                        let width = scope.columns.len();
+                        scope = scope.joined(other);
+                        records =
+                            self.outer_join(&scope, records, others, width, condition, preserved)?
+                    }
+                    join_operator => {
+                        return Err(ErrorEvent::UnimplementedBranch(format!(
+                            "UNIMPLEMENTED HANDLING OF \n{:?}\n JOIN",
+                            join_operator
+                        )))
+                    }
+                }
+            }
+        }
+        analysis.record("scan", records.len(), started);
+        if let Some(selection) = selection {
+            let started = Instant::now();
+            records = if has_subquery(selection) {
+                self.correlated_filter(records, selection, &scope)?
+            } else {
+                filter(records, selection, &scope, self.collation, &self.operators)?
+            };
+            analysis.record("filter", records.len(), started);
+        }
+        Ok((scope, records))
+    }
+
+    /// rows scanned after combining `records` with `others`, checked against the limit
+    fn scan(&self, scanned: usize, records: usize, others: usize) -> Result<usize, ErrorEvent> {
+        let scanned = scanned.saturating_add(records.saturating_mul(others));
+        match self.max_scanned_rows {
+            Some(limit) if scanned > limit => Err(ErrorEvent::ScanLimitExceeded(limit)),
+            _ => Ok(scanned),
+        }
+    }
+
+    /// filters records by a condition whose subqueries are run for every record,
+    /// as they may reference its columns
+    fn correlated_filter(
+        &self,
+        records: Vec<Vec<Type>>,
+        condition: &Expr,
+        scope: &Scope,
+    ) -> Result<Vec<Vec<Type>>, ErrorEvent> {
+        let mut filtered = vec![];
+        for record in records {
+            let correlated = self.correlate(condition, scope, &record)?;
+            if satisfies(&correlated, scope, &record, self.collation, &self.operators)? {
+                filtered.push(record);
+            }
+        }
+        Ok(filtered)
+    }
+
+    /// replaces scalar subqueries of the expression with their values for the record
+    fn correlate(&self, expr: &Expr, scope: &Scope, record: &[Type]) -> Result<Expr, ErrorEvent> {
+        match expr {
+            Expr::Subquery(query) => Ok(Expr::Value(self.scalar(query, scope, record)?.into())),
+            Expr::Nested(expr) => Ok(Expr::Nested(Box::new(self.correlate(expr, scope, record)?))),
+            Expr::UnaryOp { op, expr } => Ok(Expr::UnaryOp {
+                op: op.clone(),
+                expr: Box::new(self.correlate(expr, scope, record)?),
+            }),
+            Expr::BinaryOp { left, op, right } => Ok(Expr::BinaryOp {
+                left: Box::new(self.correlate(left, scope, record)?),
+                op: op.clone(),
+                right: Box::new(self.correlate(right, scope, record)?),
+            }),
+            expr => Ok(expr.clone()),
+        }
+    }
+
+    /// records of both sides satisfying the condition, each pair once, followed by NULLs in place
+    /// of the other side for records of a preserved side, left, right or both, that match none
+    fn outer_join(
+        &self,
+        scope: &Scope,
+        records: Vec<Vec<Type>>,
+        others: Vec<Vec<Type>>,
+        width: usize,
+        condition: &Expr,
+        preserved: (bool, bool),
+    ) -> Result<Vec<Vec<Type>>, ErrorEvent> {
+        let mut joined = vec![];
+        let mut others_matched = vec![false; others.len()];
+        for record in records {
+            let mut matched = false;
+            for (index, other) in others.iter().enumerate() {
+                let mut row = record.clone();
+                row.extend(other.iter().cloned());
+                if satisfies(condition, scope, &row, self.collation, &self.operators)? {
+                    matched = true;
+                    others_matched[index] = true;
+                    joined.push(row);
+                }
+            }
+            if preserved.0 && !matched {
+                let mut row = record;
+                row.resize(scope.columns.len(), Type::Null);
+                joined.push(row);
+            }
+        }
+        if preserved.1 {
+            for (other, matched) in others.into_iter().zip(others_matched) {
+                if !matched {
+                    let mut row = vec![Type::Null; width];
+                    row.extend(other);
+                    joined.push(row);
+                }
+            }
+        }
+        Ok(joined)
+    }
+
+    fn scalar(&self, query: &Query, outer: &Scope, record: &[Type]) -> Result<Type, ErrorEvent> {
+        let select = match &query.body {
+            SetExpr::Select(select) if query.ctes.is_empty() => select,
+            _ => {
+                return Err(ErrorEvent::UnimplementedBranch(format!(
+                    "UNIMPLEMENTED HANDLING OF \n{}\n SUBQUERY",
+                    query
+                )))
+            }
+        };
+        let (inner, _) = self.join(&select.from, &None, &mut Analysis::default())?;
+        let mut correlated = select.deref().clone();
+        correlated.selection = correlated
+            .selection
+            .map(|selection| bind(&selection, &inner, outer, record));
+        correlated.projection = correlated
+            .projection
+            .into_iter()
+            .map(|item| match item {
+                SelectItem::UnnamedExpr(expr) => {
+                    SelectItem::UnnamedExpr(bind(&expr, &inner, outer, record))
+                }
+                SelectItem::ExprWithAlias { expr, alias } => SelectItem::ExprWithAlias {
+                    expr: bind(&expr, &inner, outer, record),
+                    alias,
+                },
+                item => item,
+            })
+            .collect();
+        let (_, mut records) = self.select(
+            &SetExpr::Select(Box::new(correlated)),
+            &query.order_by,
+            &mut Analysis::default(),
+        )?;
+        match (records.pop(), records.is_empty()) {
+            (Some(mut row), true) if row.len() == 1 => Ok(row.remove(0)),
+            (Some(_), false) => Err(ErrorEvent::SubqueryReturnedMultipleRows(query.to_string())),
+            (None, _) => Ok(Type::Null),
+            _ => Err(ErrorEvent::UnimplementedBranch(format!(
+                "UNIMPLEMENTED HANDLING OF \n{}\n SUBQUERY THAT IS NOT A SINGLE VALUE",
+                query
+            ))),
+        }
+    }
+
+    fn relation(&self, relation: &TableFactor) -> Result<(Scope, Vec<Vec<Type>>), ErrorEvent> {
+        match relation {
+            TableFactor::Table { name, alias, .. } => {
+                let table_name = name.to_string();
+                match self.tables.get(&table_name) {
+                    None => Err(ErrorEvent::TableDoesNotExist(table_name)),
+                    Some(table) => Ok((
+                        table.scope(
+                            &alias
+                                .as_ref()
+                                .map(|alias| alias.name.clone())
+                                .unwrap_or_else(|| table_name.clone()),
+                        ),
+                        table
+                            .rows(&table_name, self.storage.as_ref())?
+                            .into_iter()
+                            .map(|(_, record)| record)
+                            .collect(),
+                    )),
+                }
+            }
+            relation => Err(ErrorEvent::UnimplementedBranch(format!(
+                "UNIMPLEMENTED HANDLING OF \n{:?}\n IN FROM CLAUSE",
+                relation
+            ))),
+        }
+    }
+}
+
+fn query_references(query: &Query, table_name: &str) -> bool {
+    query
+        .ctes
+        .iter()
+        .any(|cte| query_references(&cte.query, table_name))
+        || references(&query.body, table_name)
+}
+
+fn references(body: &SetExpr, table_name: &str) -> bool {
+    match body {
+        SetExpr::Select(select) => {
+            select
+                .from
+                .iter()
+                .any(|TableWithJoins { relation, joins }| {
+                    relation_references(relation, table_name)
+                        || joins
+                            .iter()
+                            .any(|join| relation_references(&join.relation, table_name))
+                })
+                || select
+                    .selection
+                    .iter()
+                    .any(|selection| expr_references(selection, table_name))
+                || select.projection.iter().any(|item| match item {
+                    SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                        expr_references(expr, table_name)
+                    }
+                    _ => false,
+                })
+        }
+        SetExpr::Query(query) => query_references(query, table_name),
+        SetExpr::SetOperation { left, right, .. } => {
+            references(left, table_name) || references(right, table_name)
+        }
+        SetExpr::Values(_) => false,
+    }
+}
+
+fn relation_references(relation: &TableFactor, table_name: &str) -> bool {
+    match relation {
+        TableFactor::Table { name, .. } => name.to_string() == table_name,
+        TableFactor::Derived { subquery, .. } => query_references(subquery, table_name),
+        TableFactor::NestedJoin(table) => {
+            relation_references(&table.relation, table_name)
+                || table
+                    .joins
+                    .iter()
+                    .any(|join| relation_references(&join.relation, table_name))
+        }
+    }
+}
+
+/// whether subqueries of the expression read from the table
+fn expr_references(expr: &Expr, table_name: &str) -> bool {
+    match expr {
+        Expr::Subquery(query) | Expr::Exists(query) => query_references(query, table_name),
+        Expr::InSubquery { expr, subquery, .. } => {
+            expr_references(expr, table_name) || query_references(subquery, table_name)
+        }
+        Expr::Nested(expr)
+        | Expr::UnaryOp { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Cast { expr, .. } => expr_references(expr, table_name),
+        Expr::BinaryOp { left, right, .. } => {
+            expr_references(left, table_name) || expr_references(right, table_name)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => [expr, low, high]
+            .iter()
+            .any(|expr| expr_references(expr, table_name)),
+        Expr::InList { expr, list, .. } => {
+            expr_references(expr, table_name)
+                || list.iter().any(|item| expr_references(item, table_name))
+        }
+        Expr::Function(Function { args, .. }) => {
+            args.iter().any(|arg| expr_references(arg, table_name))
+        }
+        _ => false,
+    }
+}
+
+/// whether the expression has more than `depth` levels,
+/// descending no further than that so the check itself cannot overflow the stack
+fn nested_deeper(expr: &Expr, depth: usize) -> bool {
+    if depth == 0 {
+        return true;
+    }
+    let depth = depth - 1;
+    match expr {
+        Expr::Nested(expr)
+        | Expr::UnaryOp { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Cast { expr, .. } => nested_deeper(expr, depth),
+        Expr::BinaryOp { left, right, .. } => {
+            nested_deeper(left, depth) || nested_deeper(right, depth)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => [expr, low, high]
+            .iter()
+            .any(|expr| nested_deeper(expr, depth)),
+        Expr::InList { expr, list, .. } => {
+            nested_deeper(expr, depth) || list.iter().any(|item| nested_deeper(item, depth))
+        }
+        Expr::Function(Function { args, .. }) => args.iter().any(|arg| nested_deeper(arg, depth)),
+        _ => false,
+    }
+}
+
+fn has_subquery(expr: &Expr) -> bool {
+    match expr {
+        Expr::Subquery(_) => true,
+        Expr::Nested(expr) | Expr::UnaryOp { expr, .. } => has_subquery(expr),
+        Expr::BinaryOp { left, right, .. } => has_subquery(left) || has_subquery(right),
+        _ => false,
+    }
+}
+
+/// replaces references to columns of the outer record that are not shadowed by the inner scope
+/// with their values
+fn bind(expr: &Expr, inner: &Scope, outer: &Scope, record: &[Type]) -> Expr {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) if inner.resolve(expr).is_err() => {
+            match outer.resolve(expr) {
+                Ok(index) => Expr::Value(record[index].clone().into()),
+                Err(_) => expr.clone(),
+            }
+        }
+        Expr::Nested(expr) => Expr::Nested(Box::new(bind(expr, inner, outer, record))),
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+            op: op.clone(),
+            expr: Box::new(bind(expr, inner, outer, record)),
+        },
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(bind(left, inner, outer, record)),
+            op: op.clone(),
+            right: Box::new(bind(right, inner, outer, record)),
+        },
+        Expr::Function(function) => Expr::Function(Function {
+            args: function
+                .args
+                .iter()
+                .map(|arg| bind(arg, inner, outer, record))
+                .collect(),
+            ..function.clone()
+        }),
+        expr => expr.clone(),
+    }
+}
+
+fn fire(
+    hooks: &mut HashMap<(String, When), Vec<WriteHook>>,
+    table_name: &str,
+    when: When,
+    events: &[&WriteEvent],
+) -> Result<(), ErrorEvent> {
+    if let Some(hooks) = hooks.get_mut(&(table_name.to_owned(), when)) {
+        for event in events {
+            for hook in hooks.iter_mut() {
+                hook(event)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// a placeholder, e.g. `?` or `$1`, written where a table name is expected,
+/// found before parsing as sqlparser does not know placeholders
+fn table_parameter(sql: &str) -> Option<String> {
+    let mut unquoted = String::with_capacity(sql.len());
+    let mut quoted = false;
+    for c in sql.chars() {
+        if c == '\'' {
+            quoted = !quoted;
+        }
+        unquoted.push(if quoted { ' ' } else { c });
+    }
+    let words = unquoted
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == '(' || c == ')')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>();
+    words.windows(2).find_map(|pair| match pair {
+        [keyword, word]
+            if ["FROM", "JOIN", "INTO", "UPDATE", "TABLE"]
+                .iter()
+                .any(|table_keyword| keyword.eq_ignore_ascii_case(table_keyword))
+                && (word.starts_with('?') || word.starts_with('$')) =>
+        {
+            Some((*word).to_owned())
+        }
+        _ => None,
+    })
+}
+
+/// the rest of the statement when it starts with the keywords in any case
+fn strip_keywords<'s>(sql: &'s str, keywords: &str) -> Option<&'s str> {
+    if sql.len() >= keywords.len()
+        && sql.is_char_boundary(keywords.len())
+        && sql[..keywords.len()].eq_ignore_ascii_case(keywords)
+    {
+        Some(&sql[keywords.len()..])
+    } else {
+        None
+    }
+}
+
+/// number of rows given by a LIMIT or OFFSET, where negative counts mean none
+fn row_count(expr: &Expr) -> Result<usize, ErrorEvent> {
+    match evaluate(expr, &Scope::default(), &[])? {
+        Type::Int(count) if count.is_negative() => Ok(0),
+        Type::Int(count) => Ok(count.to_usize().unwrap_or(usize::MAX)),
+        value => Err(ErrorEvent::TypeMismatch(format!(
+            "{:?} is not a valid row count",
+            value
+        ))),
+    }
+}
+
+/// names the kind of a statement the engine can't execute by its syntax tree node
+fn unsupported(statement: Option<Statement>) -> ErrorEvent {
+    let kind = match statement {
+        Some(statement) => format!("{:?}", statement)
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect(),
+        None => "Empty".to_owned(),
+    };
+    ErrorEvent::UnsupportedStatement { kind }
+}
+
+/// a row with the primary key values exists already
+fn duplicate_key(table_name: &str, tuple: &[Type]) -> ErrorEvent {
+    let values = tuple
+        .iter()
+        .map(|value| Value::from(value.clone()).to_string())
+        .collect::<Vec<String>>();
+    ErrorEvent::DuplicateKey(format!(
+        "{} already has a row with key ({})",
+        table_name,
+        values.join(", ")
+    ))
+}
+
+/// names the tuple of a multi-row INSERT the error happened in
+fn in_tuple(index: usize, error: ErrorEvent) -> ErrorEvent {
+    let located = |message: String| format!("tuple at index {}: {}", index, message);
+    match error {
+        ErrorEvent::TypeMismatch(message) => ErrorEvent::TypeMismatch(located(message)),
+        ErrorEvent::EncodingError(message) => ErrorEvent::EncodingError(located(message)),
+        ErrorEvent::ColumnCountMismatch(message) => {
+            ErrorEvent::ColumnCountMismatch(located(message))
+        }
+        ErrorEvent::UnimplementedBranch(message) => {
+            ErrorEvent::UnimplementedBranch(located(message))
+        }
+        ErrorEvent::DuplicateKey(message) => ErrorEvent::DuplicateKey(located(message)),
+        error => error,
+    }
+}
+
+fn coerce(value: Type, data_type: Option<&DataType>) -> Result<Type, ErrorEvent> {
+    match (value, data_type) {
+        (Type::VarChar(text), Some(DataType::Custom(name)))
+            if name.to_string().eq_ignore_ascii_case("json") =>
+        {
+            Ok(Type::json(&text)?)
+        }
+        (Type::Int(value), Some(data_type)) if tag_of(data_type) == Ok(TypeTag::Decimal) => {
+            Ok(Type::Decimal(BigDecimal::from(value)))
+        }
+        (value, _) => Ok(value),
+    }
+}
+
+/// whether a column of the declared type can hold the value,
+/// columns of types without a tag hold any value and every column holds NULL
+fn check_type(
+    value: &Type,
+    column_name: &str,
+    data_type: Option<&DataType>,
+) -> Result<(), ErrorEvent> {
+    match data_type.map(tag_of) {
+        Some(Ok(tag)) if *value != Type::Null && tag != value.tag() => {
+            Err(ErrorEvent::TypeMismatch(format!(
+                "column {} of type {} can't hold {}",
+                column_name,
+                data_type.unwrap(),
+                Value::from(value.clone())
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn filter(
+    records: Vec<Vec<Type>>,
+    condition: &Expr,
+    scope: &Scope,
+    collation: Collation,
+    operators: &Operators,
+) -> Result<Vec<Vec<Type>>, ErrorEvent> {
+    let mut filtered = vec![];
+    for record in records {
+        if satisfies(condition, scope, &record, collation, operators)? {
+            filtered.push(record);
+        }
+    }
+    Ok(filtered)
+}
+
+fn satisfies(
+    condition: &Expr,
+    scope: &Scope,
+    record: &[Type],
+    collation: Collation,
+    operators: &Operators,
+) -> Result<bool, ErrorEvent> {
+    match condition {
+        Expr::Nested(condition) => satisfies(condition, scope, record, collation, operators),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => Ok(satisfies(left, scope, record, collation, operators)?
+            && satisfies(right, scope, record, collation, operators)?),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Or,
+            right,
+        } => Ok(satisfies(left, scope, record, collation, operators)?
+            || satisfies(right, scope, record, collation, operators)?),
+        // sqlparser does not know `column MATCH 'terms'`, so it is written as a function
+        Expr::Function(Function { name, args, .. })
+            if name.to_string().eq_ignore_ascii_case("text_search") =>
+        {
+            match args.as_slice() {
+                [text, query] => match evaluate(query, scope, record)? {
+                    Type::VarChar(query) => Ok(evaluate(text, scope, record)?.contains_terms(
+                        &query
+                            .split_whitespace()
+                            .map(str::to_owned)
+                            .collect::<Vec<String>>(),
+                    )?),
+                    query => Err(ErrorEvent::TypeMismatch(format!(
+                        "{:?} is not a text search query",
+                        query
+                    ))),
+                },
+                args => Err(ErrorEvent::UnimplementedBranch(format!(
+                    "UNIMPLEMENTED HANDLING OF FUNCTION \ntext_search({:?})\n",
+                    args
+                ))),
+            }
+        }
+        Expr::Function(Function { name, args, .. })
+            if name.to_string().eq_ignore_ascii_case("array_contains") =>
+        {
+            match args.as_slice() {
+                [list, value] => Ok(evaluate(list, scope, record)?
+                    .array_contains(&evaluate(value, scope, record)?)?),
+                args => Err(ErrorEvent::UnimplementedBranch(format!(
+                    "UNIMPLEMENTED HANDLING OF FUNCTION \narray_contains({:?})\n",
+                    args
+                ))),
+            }
+        }
+        Expr::Function(Function { name, args, .. })
+            if operators.contains_key(&name.to_string().to_lowercase()) =>
+        {
+            match args.as_slice() {
+                [left, right] => Ok(operators[&name.to_string().to_lowercase()](
+                    &evaluate(left, scope, record)?,
+                    &evaluate(right, scope, record)?,
+                )),
+                args => Err(ErrorEvent::UnimplementedBranch(format!(
+                    "UNIMPLEMENTED HANDLING OF OPERATOR \n{}({:?})\n",
+                    name, args
+                ))),
+            }
+        }
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } => Ok(compare(left, right, scope, record, collation)? == Some(Ordering::Equal)),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Lt,
+            right,
+        } => Ok(compare(left, right, scope, record, collation)? == Some(Ordering::Less)),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Gt,
+            right,
+        } => Ok(compare(left, right, scope, record, collation)? == Some(Ordering::Greater)),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::NotEq,
+            right,
+        } => Ok(matches!(
+            compare(left, right, scope, record, collation)?,
+            Some(Ordering::Less) | Some(Ordering::Greater)
+        )),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::LtEq,
+            right,
+        } => Ok(matches!(
+            compare(left, right, scope, record, collation)?,
+            Some(Ordering::Less) | Some(Ordering::Equal)
+        )),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::GtEq,
+            right,
+        } => Ok(matches!(
+            compare(left, right, scope, record, collation)?,
+            Some(Ordering::Greater) | Some(Ordering::Equal)
+        )),
+        Expr::BinaryOp {
+            left,
+            op: op @ BinaryOperator::Like,
+            right,
+        }
+        | Expr::BinaryOp {
+            left,
+            op: op @ BinaryOperator::NotLike,
+            right,
+        } => match evaluate(right, scope, record)? {
+            Type::VarChar(pattern) => {
+                Ok(evaluate(left, scope, record)?.like(&pattern)? == (*op == BinaryOperator::Like))
+            }
+            pattern => Err(ErrorEvent::TypeMismatch(format!(
+                "{:?} is not a LIKE pattern",
+                pattern
+            ))),
+        },
+        Expr::IsNull(expr) => Ok(evaluate(expr, scope, record)? == Type::Null),
+        Expr::IsNotNull(expr) => Ok(evaluate(expr, scope, record)? != Type::Null),
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => match (
+            compare(expr, low, scope, record, collation)?,
+            compare(expr, high, scope, record, collation)?,
+        ) {
+            (Some(from_low), Some(to_high)) => {
+                Ok((from_low != Ordering::Less && to_high != Ordering::Greater) != *negated)
+            }
+            _ => Ok(false),
+        },
+        // a NULL in the list makes NOT IN unknown for every value that is not listed
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let mut unknown = false;
+            for item in list {
+                match compare(expr, item, scope, record, collation)? {
+                    Some(Ordering::Equal) => return Ok(!*negated),
+                    None => unknown = true,
+                    Some(_) => {}
+                }
+            }
+            Ok(*negated && !unknown)
+        }
+        // an unknown value is not true, as with comparisons to NULL
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) | Expr::Value(Value::Boolean(_)) => {
+            match evaluate(condition, scope, record)? {
+                Type::Bool(value) => Ok(value),
+                Type::Null => Ok(false),
+                value => Err(ErrorEvent::TypeMismatch(format!(
+                    "{:?} is not a condition",
+                    value
+                ))),
+            }
+        }
+        condition => Err(ErrorEvent::UnimplementedBranch(format!(
+            "UNIMPLEMENTED HANDLING OF \n{:?}\n CONDITION",
+            condition
+        ))),
+    }
+}
+
+/// ordering of the values of both sides, none when either is NULL
+/// as comparing with an unknown value is unknown, which no row satisfies
+fn compare(
+    left: &Expr,
+    right: &Expr,
+    scope: &Scope,
+    record: &[Type],
+    collation: Collation,
+) -> Result<Option<Ordering>, ErrorEvent> {
+    match (
+        evaluate(left, scope, record)?,
+        evaluate(right, scope, record)?,
+    ) {
+        (Type::Null, _) | (_, Type::Null) => Ok(None),
+        (left, right) => Ok(Some(collation.compare(&left, &right))),
+    }
+}
+
+fn evaluate(expr: &Expr, scope: &Scope, record: &[Type]) -> Result<Type, ErrorEvent> {
+    match expr {
+        Expr::Value(value) => Ok(Type::try_from(value.clone())?),
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+            Ok(record[scope.resolve(expr)?].clone())
+        }
+        Expr::Nested(expr) => evaluate(expr, scope, record),
+        Expr::Cast { expr, data_type } => {
+            match (evaluate(expr, scope, record)?, tag_of(data_type)?) {
+                (value, target) if value.tag() == target => Ok(value),
+                (Type::VarChar(raw), target) => Ok(Type::parse(&raw, target)?),
+                (Type::Int(value), TypeTag::Decimal) => Ok(Type::Decimal(BigDecimal::from(value))),
+                (value, target) => Err(ErrorEvent::TypeMismatch(format!(
+                    "CAST of {:?} to {:?} is not supported",
+                    value.tag(),
+                    target
+                ))),
+            }
+        }
+        Expr::Function(Function { name, args, .. }) => {
+            let mut values = vec![];
+            for arg in args {
+                values.push(evaluate(arg, scope, record)?);
+            }
+            match (name.to_string().to_lowercase().as_str(), values.as_slice()) {
+                ("json_extract", [json, Type::VarChar(path)]) => Ok(json.json_extract(path)?),
+                // NULL arguments are left out, so only NULL arguments give NULL
+                ("greatest", [_, ..]) => Ok(values
+                    .into_iter()
+                    .filter(|value| *value != Type::Null)
+                    .max_by(|left, right| Collation::Binary.compare(left, right))
+                    .unwrap_or(Type::Null)),
+                ("least", [_, ..]) => Ok(values
+                    .into_iter()
+                    .filter(|value| *value != Type::Null)
+                    .min_by(|left, right| Collation::Binary.compare(left, right))
+                    .unwrap_or(Type::Null)),
+                // the first argument, unless both are the same value
+                ("nullif", [value, other]) if value == other => Ok(Type::Null),
+                ("nullif", [value, _]) => Ok(value.clone()),
+                (name, _) => Err(ErrorEvent::UnimplementedBranch(format!(
+                    "UNIMPLEMENTED HANDLING OF FUNCTION \n{}({:?})\n",
+                    name, values
+                ))),
+            }
+        }
+        Expr::UnaryOp {
+            op: UnaryOperator::Plus,
+            expr,
+        } => evaluate(expr, scope, record),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => Ok((&Type::Int(BigInt::from(0)) - &evaluate(expr, scope, record)?)?),
+        Expr::BinaryOp { left, op, right } => {
+            let left = evaluate(left, scope, record)?;
+            let right = evaluate(right, scope, record)?;
+            match op {
+                BinaryOperator::Plus => Ok((&left + &right)?),
+                BinaryOperator::Minus => Ok((&left - &right)?),
+                BinaryOperator::Multiply => Ok((&left * &right)?),
+                // a comparison with NULL is unknown
+                BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Lt
+                | BinaryOperator::LtEq
+                | BinaryOperator::Gt
+                | BinaryOperator::GtEq
+                    if left == Type::Null || right == Type::Null =>
+                {
+                    Ok(Type::Null)
+                }
+                BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Lt
+                | BinaryOperator::LtEq
+                | BinaryOperator::Gt
+                | BinaryOperator::GtEq => {
+                    let ordering = Collation::Binary.compare(&left, &right);
+                    Ok(Type::Bool(match op {
+                        BinaryOperator::Eq => ordering == Ordering::Equal,
+                        BinaryOperator::NotEq => ordering != Ordering::Equal,
+                        BinaryOperator::Lt => ordering == Ordering::Less,
+                        BinaryOperator::LtEq => ordering != Ordering::Greater,
+                        BinaryOperator::Gt => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less,
+                    }))
+                }
+                operator => Err(ErrorEvent::UnsupportedOperator(operator.to_string())),
+            }
+        }
+        expr => Err(ErrorEvent::UnimplementedBranch(format!(
+            "UNIMPLEMENTED HANDLING OF \n{:?}\n EXPRESSION",
+            expr
+        ))),
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self {
+            dialect: GenericDialect {},
+            tables: HashMap::new(),
+            collation: Collation::Binary,
+            operators: HashMap::new(),
+            hooks: HashMap::new(),
+            cache: None,
+            max_scanned_rows: None,
+            max_expression_depth: DEFAULT_MAX_EXPRESSION_DEPTH,
+            max_indexes: None,
+            transaction: None,
+            storage: relational_storage::in_memory(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// rows of the expected values, compared regardless of their column names
+    fn rows(records: Vec<Vec<Type>>) -> Vec<Row> {
+        records.into_iter().map(Row::from).collect()
+    }
+
+    #[cfg(test)]
+    mod data_definition_language {
+        use super::*;
+
+        #[test]
+        fn create_two_tables() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute(
+                    "CREATE TABLE simple_table (\n\
+              int_column INT,\n\
+          );"
+                    .to_owned()
+                ),
+                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute(
+                    "CREATE TABLE another_table (\n\
+              int_column INT,\n\
+          );"
+                    .to_owned()
+                ),
+                Ok(EngineEvent::TableCreated("another_table".to_owned()))
+            );
+        }
+
+        #[test]
+        fn create_indexes_up_to_the_limit() {
+            let mut engine = Engine::default();
+            engine.set_max_indexes(2);
+            assert_eq!(
+                engine.execute("CREATE TABLE points (x INT, y INT, z INT);".to_owned()),
+                Ok(EngineEvent::TableCreated("points".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("CREATE INDEX points_x ON points (x);".to_owned()),
+                Ok(EngineEvent::IndexCreated("points".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("create index points_y on points (y)".to_owned()),
+                Ok(EngineEvent::IndexCreated("points".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("CREATE INDEX points_z ON points (z);".to_owned()),
+                Err(ErrorEvent::IndexLimitExceeded(2))
+            );
+            assert_eq!(
+                engine.execute("CREATE INDEX points_x_again ON points (x);".to_owned()),
+                Ok(EngineEvent::IndexCreated("points".to_owned()))
+            );
+        }
+
+        #[test]
+        fn create_index_on_missing_column() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE points (x INT);".to_owned()),
+                Ok(EngineEvent::TableCreated("points".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("CREATE INDEX points_w ON points (w);".to_owned()),
+                Err(ErrorEvent::ColumnDoesNotExist("w".to_owned()))
+            );
+            assert!(matches!(
+                engine.execute("CREATE INDEX points (x);".to_owned()),
+                Err(ErrorEvent::ParseError(_))
+            ));
+        }
+
+        #[test]
+        fn show_columns() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute(
+                    "CREATE TABLE people (\n\
+              id INT PRIMARY KEY,\n\
+              name VARCHAR(20) NOT NULL,\n\
+              age INT DEFAULT 18\n\
+          );"
+                    .to_owned()
+                ),
+                Ok(EngineEvent::TableCreated("people".to_owned()))
+            );
+
+            let row = |values: &[&str]| {
+                values
+                    .iter()
+                    .map(|value| Type::VarChar((*value).to_owned()))
+                    .collect::<Vec<Type>>()
+            };
+            assert_eq!(
+                engine.execute("SHOW COLUMNS FROM people;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    row(&["id", "int", "NO", "PRI", "NULL"]),
+                    row(&["name", "character varying(20)", "NO", "", "NULL"]),
+                    row(&["age", "int", "YES", "", "18"]),
+                ])))
+            );
+        }
+
+        #[test]
+        fn column_types() {
+            let mut engine = Engine::default();
+            assert!(engine
+                .execute(
+                    "CREATE TABLE mixed (id INT, price DECIMAL(10, 2), name VARCHAR(10), data JSON);"
+                        .to_owned()
+                )
+                .is_ok());
+
+            assert_eq!(
+                engine.column_types("mixed"),
+                Ok(vec![
+                    ("id".to_owned(), TypeTag::Int),
+                    ("price".to_owned(), TypeTag::Decimal),
+                    ("name".to_owned(), TypeTag::VarChar),
+                    ("data".to_owned(), TypeTag::Json)
+                ])
+            );
+            assert_eq!(
+                engine.column_types("people"),
+                Err(ErrorEvent::TableDoesNotExist("people".to_owned()))
+            );
+        }
+
+        #[test]
+        fn show_columns_of_not_existed_table() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute("SHOW COLUMNS FROM people;".to_owned()),
+                Err(ErrorEvent::TableDoesNotExist("people".to_owned()))
+            );
+        }
+
+        #[test]
+        fn error_when_trying_to_create_table_with_existing_name() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute(
+                    "CREATE TABLE simple_table (\n\
+            int_column INT,\n\
+          );"
+                    .to_owned()
+                ),
+                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute(
+                    "CREATE TABLE simple_table (\n\
+            int_column INT,\n\
+          );"
+                    .to_owned()
+                ),
+                Err(ErrorEvent::TableAlreadyExists("simple_table".to_owned()))
+            );
+        }
+
+        #[test]
+        fn create_table_if_not_exists() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute(
+                    "CREATE TABLE IF NOT EXISTS simple_table (\n\
+            int_column INT,\n\
+          );"
+                    .to_owned()
+                ),
+                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+            );
+        }
+
+        #[test]
+        fn create_table_if_not_exists_with_other_columns() {
+            let mut engine = Engine::default();
+            let create = |engine: &mut Engine, columns: &str| {
+                engine.execute(format!(
+                    "CREATE TABLE IF NOT EXISTS simple_table ({});",
+                    columns
+                ))
+            };
+
+            assert_eq!(
+                create(&mut engine, "id INT, name VARCHAR(10)"),
+                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+            );
+            assert_eq!(
+                create(&mut engine, "id INT, name VARCHAR(10)"),
+                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+            );
+            assert_eq!(
+                create(&mut engine, "id INT, name VARCHAR(10), age INT"),
+                Err(ErrorEvent::SchemaConflict(
+                    "simple_table has columns (id int, name character varying(10)) \
+                     but (id int, name character varying(10), age int) were given"
+                        .to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn dump_and_reload_table() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20) NOT NULL);",
+                "CREATE TABLE orders (id INT, amount DECIMAL(10, 2));",
+                "INSERT INTO users VALUES (1, 'alice');",
+                "INSERT INTO users VALUES (2, 'o''brien');",
+                "INSERT INTO orders VALUES (1, 9.5);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            let dump = engine.dump_table("users").expect("users are dumped");
+            assert_eq!(
+                dump,
+                "CREATE TABLE users (id int PRIMARY KEY, name character varying(20) NOT NULL);\n\
+                 INSERT INTO users VALUES (1, 'alice');\n\
+                 INSERT INTO users VALUES (2, 'o''brien');\n"
+            );
+
+            let mut reloaded = Engine::default();
+            for statement in dump.lines() {
+                assert!(reloaded.execute(statement.to_owned()).is_ok());
+            }
+            let select = "SELECT * FROM users;".to_owned();
+            assert_eq!(reloaded.execute(select.clone()), engine.execute(select));
+            assert_eq!(
+                reloaded.execute("SELECT * FROM orders;".to_owned()),
+                Err(ErrorEvent::TableDoesNotExist("orders".to_owned()))
+            );
+        }
+
+        #[test]
+        fn dump_missing_table() {
+            let engine = Engine::default();
+
+            assert_eq!(
+                engine.dump_table("missing"),
+                Err(ErrorEvent::TableDoesNotExist("missing".to_owned()))
+            );
+        }
+
+        #[test]
+        fn engine_from_schema() {
+            let mut engine = Engine::from_schema(
+                "CREATE TABLE users (id INT, name VARCHAR(20));\n\
+                 CREATE TABLE orders (id INT, user_id INT);",
+            )
+            .unwrap();
+
+            assert_eq!(
+                engine.column_types("users"),
+                Ok(vec![
+                    ("id".to_owned(), TypeTag::Int),
+                    ("name".to_owned(), TypeTag::VarChar)
+                ])
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO orders VALUES (1, 2);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+        }
+
+        #[test]
+        fn engine_from_invalid_schema() {
+            assert_eq!(
+                Engine::from_schema("CREATE TABLE t (id INT); CREATE TABLE t (id INT);").err(),
+                Some(ErrorEvent::TableAlreadyExists("t".to_owned()))
+            );
+            assert!(
+                Engine::from_schema("CREATE TABLE t (id INT); INSERT INTO t VALUES (1);").is_err()
+            );
+            assert!(Engine::from_schema("CREATE TABLE t (id INT").is_err());
+        }
+
+        #[test]
+        fn insert_validated_against_declared_columns() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE t (a INT, b INT, c VARCHAR);".to_owned()),
+                Ok(EngineEvent::TableCreated("t".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("INSERT INTO t VALUES (1, 2, 'three');".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO t VALUES (1);".to_owned()),
+                Err(ErrorEvent::ColumnCountMismatch(
+                    "t has 3 columns but 1 values were given".to_owned()
+                ))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO t VALUES (1, 'two', 'three');".to_owned()),
+                Err(ErrorEvent::TypeMismatch(
+                    "column b of type int can't hold 'two'".to_owned()
+                ))
+            );
+            assert_eq!(
+                engine.execute("SELECT a, b, c FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Int(BigInt::from(1)),
+                    Type::Int(BigInt::from(2)),
+                    Type::VarChar("three".to_owned())
+                ]])))
+            );
+        }
+
+        #[test]
+        fn insert_named_columns() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE t (a INT, b INT, c INT DEFAULT 30);".to_owned()),
+                Ok(EngineEvent::TableCreated("t".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("INSERT INTO t (c, a, b) VALUES (3, 1, 2);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO t (b, a) VALUES (20, 10);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO t (a, d) VALUES (1, 2);".to_owned()),
+                Err(ErrorEvent::ColumnDoesNotExist("d".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO t (a, c) VALUES (1, 2);".to_owned()),
+                Err(ErrorEvent::ColumnCountMismatch(
+                    "no value was given for column b without a default".to_owned()
+                ))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![
+                        Type::Int(BigInt::from(1)),
+                        Type::Int(BigInt::from(2)),
+                        Type::Int(BigInt::from(3))
+                    ],
+                    vec![
+                        Type::Int(BigInt::from(10)),
+                        Type::Int(BigInt::from(20)),
+                        Type::Int(BigInt::from(30))
+                    ]
+                ])))
+            );
+        }
+
+        #[ignore] // TODO "ALTER TABLE ... ALTER COLUMN ... TYPE" is not supported by sqlparser
+        #[test]
+        fn alter_column_type_migrates_existing_rows() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE versioned (id INT, value INT);",
+                "INSERT INTO versioned VALUES (1, 10);",
+                "ALTER TABLE versioned ALTER COLUMN value TYPE VARCHAR;",
+                "INSERT INTO versioned VALUES (2, 'twenty');",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT value FROM versioned;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::VarChar("10".to_owned())],
+                    vec![Type::VarChar("twenty".to_owned())]
+                ])))
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod data_manipulation_language {
+        use std::fmt::Display;
+
+        use num_bigint::BigInt;
+
+        use super::*;
+
+        const TABLE_NAME: &'static str = "simple_table";
+        const COLUMN_NAME: &'static str = "int_column";
+
+        #[allow(unused_must_use)]
+        fn create_table(engine: &mut Engine) {
+            engine.execute(format!(
+                "CREATE TABLE {} ({} INT);",
+                TABLE_NAME, COLUMN_NAME
+            ));
+        }
+
+        fn insert_value<V: Display>(engine: &mut Engine, value: V) -> ExecutionResult {
+            engine.execute(format!("INSERT INTO {} VALUES ({});", TABLE_NAME, value))
+        }
+
+        fn select_value<V: Display>(engine: &mut Engine, value: V) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} = {2};",
+                COLUMN_NAME, TABLE_NAME, value
+            ))
+        }
+
+        fn select_all(engine: &mut Engine) -> ExecutionResult {
+            engine.execute(format!("SELECT {} FROM {};", COLUMN_NAME, TABLE_NAME))
+        }
+
+        fn select_between<V: Display>(engine: &mut Engine, from: V, to: V) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} BETWEEN {2} AND {3}",
+                COLUMN_NAME, TABLE_NAME, from, to
+            ))
+        }
+
+        fn select_not_between<V: Display>(engine: &mut Engine, from: V, to: V) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} NOT BETWEEN {2} AND {3}",
+                COLUMN_NAME, TABLE_NAME, from, to
+            ))
+        }
+
+        fn select_in<V: Display>(engine: &mut Engine, one: V, two: V, three: V) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} IN ({2}, {3}, {4});",
+                COLUMN_NAME, TABLE_NAME, one, two, three
+            ))
+        }
+
+        fn select_not_in<V: Display>(
+            engine: &mut Engine,
+            one: V,
+            two: V,
+            three: V,
+        ) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} NOT IN ({2}, {3}, {4});",
+                COLUMN_NAME, TABLE_NAME, one, two, three
+            ))
+        }
+
+        fn select_with_and<V: Display>(engine: &mut Engine, one: V, two: V) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} = {2} AND {0} = {3};",
+                COLUMN_NAME, TABLE_NAME, one, two
+            ))
+        }
+
+        fn select_with_or<V: Display>(engine: &mut Engine, one: V, two: V) -> ExecutionResult {
+            engine.execute(format!(
+                "SELECT {0} FROM {1} WHERE {0} = {2} OR {0} = {3};",
+                COLUMN_NAME, TABLE_NAME, one, two
+            ))
+        }
+
+        fn update_value<V: Display>(engine: &mut Engine, from: V, to: V) -> ExecutionResult {
+            engine.execute(format!(
+                "UPDATE {0} SET {1} = {2} where {1} = {3}",
+                TABLE_NAME, COLUMN_NAME, from, to
+            ))
+        }
+
+        fn update_all(engine: &mut Engine) -> ExecutionResult {
+            engine.execute(format!("UPDATE {0} SET {1} = 100", TABLE_NAME, COLUMN_NAME))
+        }
+
+        fn delete_value<V: Display>(engine: &mut Engine, value: V) -> ExecutionResult {
+            engine.execute(format!(
+                "DELETE FROM {} WHERE {} = {}",
+                TABLE_NAME, COLUMN_NAME, value
+            ))
+        }
+
+        fn delete_all(engine: &mut Engine) -> ExecutionResult {
+            engine.execute(format!("DELETE FROM {}", TABLE_NAME))
+        }
+
+        fn int(val: i32) -> Vec<Type> {
+            vec![Type::Int(BigInt::from(val))]
+        }
+
+        fn ints(values: &[i32]) -> Vec<Type> {
+            values
+                .iter()
+                .map(|value| Type::Int(BigInt::from(*value)))
+                .collect()
+        }
+
+        #[test]
+        fn insert_into_not_existed_table() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            )
+        }
+
+        #[test]
+        fn select_from_not_existed_table() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                select_all(&mut engine),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            )
+        }
+
+        #[test]
+        fn update_from_not_existed_table() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine
+                    .storage
+                    .update(TABLE_NAME, (COLUMN_NAME.to_owned(), Type::Null), None),
+                Err(SqlError::TableDoesNotExists)
+            );
+            assert_eq!(
+                update_all(&mut engine),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            );
+            assert_eq!(
+                update_value(&mut engine, 2, 1),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            )
+        }
+
+        #[test]
+        fn delete_from_not_existed_table() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.storage.delete(TABLE_NAME, None),
+                Err(SqlError::TableDoesNotExists)
+            );
+            assert_eq!(
+                delete_all(&mut engine),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            );
+            assert_eq!(
+                delete_value(&mut engine, 1),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            )
+        }
+
+        #[test]
+        fn insert_select_single_record() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
+        }
+
+        #[test]
+        fn insert_many_select_single_record() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2)])))
+            );
+        }
+
+        #[test]
+        fn insert_many_select_all_records() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(2),
+                    int(3)
+                ])))
+            );
+        }
+
+        #[test]
+        fn insert_many_tuples_in_one_statement() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                engine.execute(format!("INSERT INTO {} VALUES (1), (2), (3);", TABLE_NAME)),
+                Ok(EngineEvent::RecordsInserted(3))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(2),
+                    int(3)
+                ])))
+            );
+        }
+
+        #[test]
+        fn insert_many_tuples_failing_in_the_middle() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                engine.execute(format!(
+                    "INSERT INTO {} VALUES (1), ('two'), (3);",
+                    TABLE_NAME
+                )),
+                Err(ErrorEvent::TypeMismatch(format!(
+                    "tuple at index 1: column {} of type int can't hold 'two'",
+                    COLUMN_NAME
+                )))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
+        }
+
+        #[test]
+        fn update_single_value() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                update_value(&mut engine, 4, 2),
+                Ok(EngineEvent::RecordsUpdated(1))
+            );
+            assert_eq!(
+                update_value(&mut engine, 5, 9),
+                Ok(EngineEvent::RecordsUpdated(0))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(4),
+                    int(3)
+                ])))
+            );
+        }
+
+        #[test]
+        fn update_is_written_to_storage() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                update_value(&mut engine, 4, 2),
+                Ok(EngineEvent::RecordsUpdated(1))
+            );
+            assert_eq!(
+                engine.storage.select(
+                    &TABLE_NAME.to_owned(),
+                    Some(Predicate::ColumnEqual(
+                        COLUMN_NAME.to_owned(),
+                        int(4).remove(0)
+                    ))
+                ),
+                Ok(vec![ints(&[1, 4])])
+            );
+        }
+
+        #[test]
+        fn update_all_values() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(update_all(&mut engine), Ok(EngineEvent::RecordsUpdated(3)));
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(100),
+                    int(100),
+                    int(100)
+                ])))
+            );
+        }
+
+        #[test]
+        fn update_with_arithmetic_expression() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                engine.execute(format!(
+                    "UPDATE {} SET {} = (2 + 3) * 4 - 1",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::RecordsUpdated(1))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(19)])))
+            );
+        }
+
+        #[test]
+        fn update_with_mismatched_types() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                engine.execute(format!(
+                    "UPDATE {} SET {} = 1 + 'one'",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Err(ErrorEvent::TypeMismatch(
+                    "Int + VarChar is not supported".to_owned()
+                ))
+            );
+        }
+
+        #[ignore] // TODO "ON DELETE CASCADE" is not supported by sqlparser
+        #[test]
+        fn delete_cascades_to_referencing_rows() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE parents (id INT PRIMARY KEY);",
+                "CREATE TABLE children (id INT, parent_id INT REFERENCES parents (id) ON DELETE CASCADE);",
+                "INSERT INTO parents VALUES (1);",
+                "INSERT INTO parents VALUES (2);",
+                "INSERT INTO children VALUES (10, 1);",
+                "INSERT INTO children VALUES (20, 2);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("DELETE FROM parents WHERE id = 1;".to_owned()),
+                Ok(EngineEvent::RecordsDeleted(1))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM children;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[20, 2])])))
+            );
+        }
+
+        #[ignore] // TODO "UPDATE ... FROM" is not supported by sqlparser
+        #[test]
+        fn update_from_source_table() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE target (k INT, v INT);",
+                "CREATE TABLE source (k INT, v INT);",
+                "INSERT INTO target VALUES (1, 10);",
+                "INSERT INTO target VALUES (2, 20);",
+                "INSERT INTO source VALUES (2, 200);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute(
+                    "UPDATE target SET v = s.v FROM source s WHERE target.k = s.k;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsUpdated(1))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM target;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 10]),
+                    ints(&[2, 200])
+                ])))
+            );
+        }
+
+        #[ignore] // TODO "DELETE ... USING" is not supported by sqlparser
+        #[test]
+        fn delete_using_other_table() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE target (k INT, v INT);",
+                "CREATE TABLE other (k INT);",
+                "INSERT INTO target VALUES (1, 10);",
+                "INSERT INTO target VALUES (2, 20);",
+                "INSERT INTO target VALUES (3, 30);",
+                "INSERT INTO other VALUES (2);",
+                "INSERT INTO other VALUES (3);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine
+                    .execute("DELETE FROM target USING other WHERE target.k = other.k;".to_owned()),
+                Ok(EngineEvent::RecordsDeleted(2))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM target;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[1, 10])])))
+            );
+        }
+
+        #[test]
+        fn update_swaps_columns() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, a INT, b INT);",
+                "INSERT INTO pairs VALUES (1, 10, 20);",
+                "INSERT INTO pairs VALUES (2, 30, 40);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("UPDATE pairs SET a = b, b = a;".to_owned()),
+                Ok(EngineEvent::RecordsUpdated(2))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 20, 10]),
+                    ints(&[2, 40, 30])
+                ])))
+            );
+        }
+
+        #[test]
+        fn store_and_extract_json() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE documents (id INT, data JSON);".to_owned()),
+                Ok(EngineEvent::TableCreated("documents".to_owned()))
+            );
+            assert_eq!(
+                engine.execute(
+                    r#"INSERT INTO documents VALUES (1, '{"user": {"name": "alex", "age": 30}}');"#
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT id, json_extract(data, '$.user.name'), json_extract(data, '$.user.age') \
+                     FROM documents;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Int(BigInt::from(1)),
+                    Type::VarChar("alex".to_owned()),
+                    Type::Int(BigInt::from(30))
+                ]])))
+            );
+        }
+
+        #[test]
+        fn select_where_json_field() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE documents (id INT, data JSON);",
+                r#"INSERT INTO documents VALUES (1, '{"status": "active"}');"#,
+                r#"INSERT INTO documents VALUES (2, '{"status": "disabled"}');"#,
+                r#"INSERT INTO documents VALUES (3, '{"status": "active"}');"#,
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT id FROM documents WHERE json_extract(data, '$.status') = 'active';"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+        }
+
+        #[ignore] // TODO "ARRAY[...]" and "INT[]" are not supported by sqlparser
+        #[test]
+        fn store_and_filter_arrays() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE tagged (id INT, tags INT[]);",
+                "INSERT INTO tagged VALUES (1, ARRAY[1, 2, 3]);",
+                "INSERT INTO tagged VALUES (2, ARRAY[4]);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT tags FROM tagged WHERE id = 1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![Type::List(
+                    ints(&[1, 2, 3])
+                )]])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM tagged WHERE array_contains(tags, 4);".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2)])))
+            );
+        }
+
+        // there is no index over array elements, so every row is scanned
+        #[ignore] // TODO "@>" and "ARRAY[...]" are not supported by sqlparser
+        #[test]
+        fn filter_arrays_containing_subset() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE tagged (id INT, tags INT[]);",
+                "INSERT INTO tagged VALUES (1, ARRAY[1, 2, 3]);",
+                "INSERT INTO tagged VALUES (2, ARRAY[2, 4]);",
+                "INSERT INTO tagged VALUES (3, ARRAY[1, 2]);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM tagged WHERE tags @> ARRAY[1, 2];".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+        }
+
+        #[test]
+        fn insert_select_from_values_cte() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                engine.execute(format!(
+                    "INSERT INTO {} WITH tmp(a) AS (VALUES (1), (2)) SELECT a FROM tmp;",
+                    TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsInserted(2))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+        }
+
+        #[ignore] // TODO "WITH ... INSERT" is not supported by sqlparser
+        #[test]
+        fn with_values_cte_insert_select() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                engine.execute(format!(
+                    "WITH tmp(a) AS (VALUES (1), (2)) INSERT INTO {} SELECT a FROM tmp;",
+                    TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsInserted(2))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+        }
+
+        #[ignore] // TODO "INSERT IGNORE" is not supported by sqlparser
+        #[test]
+        fn insert_ignore_skips_duplicate_keys() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT PRIMARY KEY, value INT);",
+                "INSERT INTO pairs VALUES (2, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute(
+                    "INSERT IGNORE INTO pairs SELECT * FROM (VALUES (1, 10), (2, 0), (3, 30)) AS t;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("SELECT value FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(10),
+                    int(20),
+                    int(30)
+                ])))
+            );
+        }
+
+        #[test]
+        fn varchar_column() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE names (name VARCHAR, rank INT);",
+                "INSERT INTO names VALUES ('def', 2);",
+                "INSERT INTO names VALUES ('abc', 1);",
+                "INSERT INTO names VALUES ('abc', 3);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT rank FROM names WHERE name = 'abc';".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("UPDATE names SET rank = 0 WHERE name = 'def';".to_owned()),
+                Ok(EngineEvent::RecordsUpdated(1))
+            );
+            assert_eq!(
+                engine.execute("DELETE FROM names WHERE rank = 1;".to_owned()),
+                Ok(EngineEvent::RecordsDeleted(1))
+            );
+            assert_eq!(
+                engine.execute("SELECT name, rank FROM names;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::VarChar("def".to_owned()), Type::Int(BigInt::from(0))],
+                    vec![Type::VarChar("abc".to_owned()), Type::Int(BigInt::from(3))]
+                ])))
+            );
+        }
+
+        #[test]
+        fn query_cache_is_invalidated_by_writes() {
+            let mut engine = Engine::default();
+            engine.enable_query_cache(2);
+            create_table(&mut engine);
+            assert!(engine
+                .execute("CREATE TABLE other (id INT);".to_owned())
+                .is_ok());
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
+            assert_eq!(engine.cache.as_ref().unwrap().entries.len(), 1);
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
+
+            assert!(engine
+                .execute("INSERT INTO other VALUES (1);".to_owned())
+                .is_ok());
+            assert_eq!(engine.cache.as_ref().unwrap().entries.len(), 1);
+
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert!(engine.cache.as_ref().unwrap().entries.is_empty());
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+        }
+
+        #[test]
+        fn query_cache_evicts_least_recently_used() {
+            let mut engine = Engine::default();
+            engine.enable_query_cache(2);
+            create_table(&mut engine);
+
+            for query in &[
+                "SELECT int_column FROM simple_table;",
+                "VALUES (1);",
+                "SELECT int_column FROM simple_table;",
+                "VALUES (2);",
+            ] {
+                assert!(engine.execute((*query).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine
+                    .cache
+                    .as_ref()
+                    .unwrap()
+                    .entries
+                    .iter()
+                    .map(|(key, _, _)| key.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["SELECT int_column FROM simple_table", "VALUES (2)"]
+            );
+        }
+
+        #[test]
+        fn text_search_of_all_terms() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE articles (id INT, title VARCHAR(100));",
+                "INSERT INTO articles VALUES (1, 'Foo and Bar');",
+                "INSERT INTO articles VALUES (2, 'only foo');",
+                "INSERT INTO articles VALUES (3, 'BAR FOO');",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT id FROM articles WHERE text_search(title, 'foo bar');".to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+        }
+
+        #[test]
+        fn custom_operator() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE points (id INT, position INT);",
+                "INSERT INTO points VALUES (1, 10);",
+                "INSERT INTO points VALUES (2, 19);",
+                "INSERT INTO points VALUES (3, 22);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+            engine.register_operator("NEAR", |left, right| match (left, right) {
+                (Type::Int(left), Type::Int(right)) => {
+                    (left - right) * (left - right) <= BigInt::from(4)
+                }
+                _ => false,
+            });
+
+            assert_eq!(
+                engine.execute("SELECT id FROM points WHERE near(position, 20);".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2), int(3)])))
+            );
+            assert!(engine
+                .execute("SELECT id FROM points WHERE far(position, 20);".to_owned())
+                .is_err());
+        }
+
+        #[test]
+        fn greatest_and_least_of_columns() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE triples (a INT, b INT, c INT);",
+                "INSERT INTO triples VALUES (1, 5, 3);",
+                "INSERT INTO triples VALUES (9, 2, 4);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT GREATEST(a, b, c), LEAST(a, b, c) FROM triples;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[5, 1]),
+                    ints(&[9, 2])
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT a FROM triples WHERE GREATEST(b, c) = 4;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(9)])))
+            );
+        }
+
+        #[test]
+        fn greatest_and_least_of_nulls_and_decimals() {
+            let mut engine = Engine::default();
+            for statement in &["CREATE TABLE t (id INT);", "INSERT INTO t VALUES (1);"] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT GREATEST(5, 2.5), LEAST(5, 2.5) FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Int(BigInt::from(5)),
+                    Type::Decimal("2.5".parse::<BigDecimal>().unwrap())
+                ]])))
+            );
+            assert_eq!(
+                engine.execute("SELECT GREATEST(5, NULL), LEAST(5, NULL) FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[5, 5])])))
+            );
+            assert_eq!(
+                engine.execute("SELECT GREATEST(NULL, NULL), LEAST(NULL) FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Null,
+                    Type::Null
+                ]])))
+            );
+        }
+
+        #[test]
+        fn nullif_of_equal_and_different_values() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (a INT, b INT);",
+                "INSERT INTO pairs VALUES (1, 1);",
+                "INSERT INTO pairs VALUES (2, 3);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT NULLIF(a, b), NULLIF(b, 1) FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::Null, Type::Null],
+                    ints(&[2, 3])
+                ])))
+            );
+        }
+
+        #[test]
+        fn comparison_projections() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE f (id INT, a INT);",
+                "INSERT INTO f VALUES (1, 1);",
+                "INSERT INTO f VALUES (2, 3);",
+                "INSERT INTO f VALUES (3, NULL);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT a > 1, a = 1, a <= 2.5 FROM f;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::Bool(false), Type::Bool(true), Type::Bool(true)],
+                    vec![Type::Bool(true), Type::Bool(false), Type::Bool(false)],
+                    vec![Type::Null, Type::Null, Type::Null]
+                ])))
+            );
+        }
+
+        #[test]
+        fn before_write_hook_rejects_inserts() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            engine.on_write(TABLE_NAME, When::Before, |event| match event {
+                WriteEvent::Insert(record) if record[0] < Type::Int(BigInt::from(0)) => Err(
+                    ErrorEvent::WriteRejected("negative values are not allowed".to_owned()),
+                ),
+                _ => Ok(()),
+            });
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, -1),
+                Err(ErrorEvent::WriteRejected(
+                    "negative values are not allowed".to_owned()
+                ))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
+        }
+
+        #[test]
+        fn after_write_hook_sees_every_row() {
+            use std::cell::RefCell;
+            use std::rc::Rc;
+
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            let audit = Rc::new(RefCell::new(vec![]));
+            let log = audit.clone();
+            engine.on_write(TABLE_NAME, When::After, move |event| {
+                log.borrow_mut().push(format!("{:?}", event));
+                Ok(())
+            });
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                update_value(&mut engine, 2, 1),
+                Ok(EngineEvent::RecordsUpdated(1))
+            );
+            assert_eq!(
+                delete_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsDeleted(1))
+            );
+
+            assert_eq!(
+                *audit.borrow(),
+                vec![
+                    format!("{:?}", WriteEvent::Insert(int(1))),
+                    format!(
+                        "{:?}",
+                        WriteEvent::Update {
+                            old: int(1),
+                            new: int(2)
+                        }
+                    ),
+                    format!("{:?}", WriteEvent::Delete(int(2))),
+                ]
+            );
+        }
+
+        #[test]
+        fn insert_invalid_utf8_string() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE names (id INT, name VARCHAR);".to_owned()),
+                Ok(EngineEvent::TableCreated("names".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("INSERT INTO names VALUES (1, X'616263');".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO names VALUES (2, X'C328');".to_owned()),
+                Err(ErrorEvent::EncodingError(
+                    "[195, 40] is not a valid UTF-8 string: invalid utf-8 sequence of 1 bytes from index 0"
+                        .to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn compare_strings_with_binary_collation() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE names (id INT, name VARCHAR);",
+                "INSERT INTO names VALUES (1, 'Alex');",
+                "INSERT INTO names VALUES (2, 'alex');",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM names WHERE name = 'alex';".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2)])))
+            );
+        }
+
+        #[test]
+        fn compare_strings_with_case_insensitive_collation() {
+            let mut engine = Engine::default();
+            engine.set_collation(Collation::CaseInsensitive);
+            for statement in &[
+                "CREATE TABLE names (id INT, name VARCHAR);",
+                "INSERT INTO names VALUES (1, 'Alex');",
+                "INSERT INTO names VALUES (2, 'alex');",
+                "INSERT INTO names VALUES (3, 'Bob');",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM names WHERE name = 'ALEX';".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+        }
+
+        #[test]
+        fn insert_cast_of_unparseable_number() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                engine.execute("INSERT INTO simple_table VALUES (CAST('42' AS INT));".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO simple_table VALUES (CAST('4x2' AS INT));".to_owned()),
+                Err(ErrorEvent::ParseFailure(
+                    "4x2 is not a valid Int".to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn insert_invalid_json() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE documents (id INT, data JSON);".to_owned()),
+                Ok(EngineEvent::TableCreated("documents".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("INSERT INTO documents VALUES (1, '{');".to_owned()),
+                Err(ErrorEvent::TypeMismatch(
+                    "\"{\" is not a valid JSON: EOF while parsing an object at line 1 column 1"
+                        .to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn delete_single_value() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                delete_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsDeleted(1))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+        }
+
+        #[test]
+        fn delete_is_written_to_storage() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                delete_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsDeleted(1))
+            );
+            assert_eq!(
+                engine.storage.select(&TABLE_NAME.to_owned(), None),
+                Ok(vec![ints(&[1, 2])])
+            );
+        }
+
+        #[test]
+        fn delete_all_values() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(delete_all(&mut engine), Ok(EngineEvent::RecordsDeleted(3)));
+            assert_eq!(delete_all(&mut engine), Ok(EngineEvent::RecordsDeleted(0)));
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
+            );
+        }
+
+        #[test]
+        fn select_in_range() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 4),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 5),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_between(&mut engine, 2, 4),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(2),
+                    int(3),
+                    int(4)
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_out_range() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 4),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 5),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_not_between(&mut engine, 2, 4),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(5)])))
+            );
+        }
+
+        #[test]
+        fn select_in_enumeration() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 4),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 5),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_in(&mut engine, 1, 3, 5),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(3),
+                    int(5)
+                ])))
+            )
+        }
+
+        #[test]
+        fn insert_existing_key() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (id INT PRIMARY KEY, b INT);",
+                "INSERT INTO t VALUES (1, 30);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("INSERT INTO t VALUES (1, 99);".to_owned()),
+                Err(ErrorEvent::DuplicateKey(
+                    "t already has a row with key (1)".to_owned()
+                ))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO t VALUES (2, 20), (2, 10);".to_owned()),
+                Err(ErrorEvent::DuplicateKey(
+                    "tuple at index 1: t already has a row with key (2)".to_owned()
+                ))
+            );
+            assert_eq!(
+                engine.execute("SELECT id, b FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 30]),
+                    ints(&[2, 20])
+                ])))
+            );
+        }
+
+        #[test]
+        fn insert_repeated_first_value_without_primary_key() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (id INT, b INT);",
+                "INSERT INTO t VALUES (1, 30);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("INSERT INTO t VALUES (1, 99);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("SELECT b FROM t WHERE id = 1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(30), int(99)])))
+            );
+        }
+
+        #[test]
+        fn update_key_column() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (id INT PRIMARY KEY, b INT);",
+                "INSERT INTO t VALUES (1, 10);",
+                "INSERT INTO t VALUES (2, 20);",
+                "INSERT INTO t VALUES (3, 30);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert!(engine
+                .execute("UPDATE t SET id = 7 WHERE id = 2;".to_owned())
+                .is_ok());
+            assert_eq!(
+                engine.execute("SELECT b FROM t WHERE id = 7;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(20)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT b FROM t WHERE id = 2;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(vec![]))
+            );
+            assert_eq!(
+                engine.execute("SELECT b FROM t WHERE id > 5;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(20)])))
+            );
+
+            assert_eq!(
+                engine.execute("UPDATE t SET id = 3 WHERE id = 1;".to_owned()),
+                Err(ErrorEvent::DuplicateKey(
+                    "t already has a row with key (3)".to_owned()
+                ))
+            );
+            assert!(engine
+                .execute("DELETE FROM t WHERE id = 7;".to_owned())
+                .is_ok());
+            assert_eq!(
+                engine.execute("SELECT id, b FROM t;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 10]),
+                    ints(&[3, 30])
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_range_and_enumeration_of_non_key_column() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (id INT, b INT);",
+                "INSERT INTO t VALUES (1, 30);",
+                "INSERT INTO t VALUES (2, 10);",
+                "INSERT INTO t VALUES (3, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM t WHERE b BETWEEN 1 AND 2;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(vec![]))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM t WHERE b BETWEEN 15 AND 30;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM t WHERE b NOT BETWEEN 15 AND 30;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM t WHERE b IN (10, 20);".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM t WHERE b NOT IN (1);".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(2),
+                    int(3)
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_range_and_enumeration_of_unkeyed_table() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (name VARCHAR(10), b INT);",
+                "INSERT INTO t VALUES ('one', 30);",
+                "INSERT INTO t VALUES ('two', 10);",
+                "INSERT INTO t VALUES ('three', 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT name FROM t WHERE b BETWEEN 15 AND 30;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::VarChar("one".to_owned())],
+                    vec![Type::VarChar("three".to_owned())]
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT b FROM t WHERE name IN ('two', 'four');".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(10)])))
+            );
+        }
+
+        #[ignore] // TODO "(a, b) IN ((x, y), ...)" is not supported by sqlparser
+        #[test]
+        fn select_in_tuple_enumeration() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, a INT, b INT);",
+                "INSERT INTO pairs VALUES (1, 1, 2);",
+                "INSERT INTO pairs VALUES (2, 1, 3);",
+                "INSERT INTO pairs VALUES (3, 3, 4);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM pairs WHERE (a, b) IN ((1, 2), (3, 4));".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+        }
+
+        #[test]
+        fn select_out_of_enumeration() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 2),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 3),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 4),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 5),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                select_not_in(&mut engine, 1, 3, 5),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2), int(4)])))
+            )
+        }
+
+        #[test]
+        fn select_from_values_list() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute("VALUES (1), (2), (3);".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(2),
+                    int(3)
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_from_values_list_with_many_columns() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute("VALUES (1, 'one'), (2, 'two');".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::Int(BigInt::from(1)), Type::VarChar("one".to_owned())],
+                    vec![Type::Int(BigInt::from(2)), Type::VarChar("two".to_owned())]
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_columns_in_reverse_order() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute("CREATE TABLE pairs (a INT, b INT);".to_owned()),
+                Ok(EngineEvent::TableCreated("pairs".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO pairs VALUES (1, 10);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO pairs VALUES (2, 20);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                engine.execute("SELECT b, a FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[10, 1]),
+                    ints(&[20, 2])
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_same_column_twice() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute("CREATE TABLE pairs (a INT, b INT);".to_owned()),
+                Ok(EngineEvent::TableCreated("pairs".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO pairs VALUES (1, 10);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            assert_eq!(
+                engine.execute("SELECT b, a, b FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[10, 1, 10])])))
+            );
+        }
+
+        #[test]
+        fn select_from_cte() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=3 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            assert_eq!(
+                engine.execute(format!(
+                    "WITH small (id) AS (SELECT {0} FROM {1} WHERE {0} BETWEEN 1 AND 2) \
+                     SELECT id FROM small;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM small;".to_owned()),
+                Err(ErrorEvent::TableDoesNotExist("small".to_owned()))
+            );
+        }
+
+        #[test]
+        fn select_repeated_values_from_cte() {
+            let mut engine = Engine::default();
+
+            assert_eq!(
+                engine.execute("WITH c(a) AS (VALUES (1), (1), (2)) SELECT a FROM c;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(1),
+                    int(2)
+                ])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "WITH c(a, b) AS (VALUES (1, 'x'), (1, 'y')) SELECT b FROM c WHERE a = 1;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::VarChar("x".to_owned())],
+                    vec![Type::VarChar("y".to_owned())]
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_from_cte_of_strings() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE s (id INT, name VARCHAR(10));",
+                "INSERT INTO s VALUES (1, 'one');",
+                "INSERT INTO s VALUES (2, 'two');",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute(
+                    "WITH names AS (SELECT name FROM s) SELECT name FROM names WHERE name = 'two';"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::VarChar("two".to_owned())
+                ]])))
+            );
+        }
+
+        #[test]
+        fn select_listed_columns_of_two_columns() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, value INT);",
+                "INSERT INTO pairs VALUES (1, 10);",
+                "INSERT INTO pairs VALUES (2, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT value FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(10), int(20)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT value, id FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[10, 1]),
+                    ints(&[20, 2])
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id, missing FROM pairs;".to_owned()),
+                Err(ErrorEvent::ColumnDoesNotExist("missing".to_owned()))
+            );
+        }
+
+        #[test]
+        fn select_wildcard_of_two_columns() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, value INT);",
+                "INSERT INTO pairs VALUES (2, 20);",
+                "INSERT INTO pairs VALUES (1, 10);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT * FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[2, 20]),
+                    ints(&[1, 10])
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT value, * FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[20, 2, 20]),
+                    ints(&[10, 1, 10])
+                ])))
+            );
+        }
+
+        #[test]
+        fn select_qualified_wildcard_from_join() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE a (id INT, x INT);",
+                "CREATE TABLE b (id INT, y INT);",
+                "INSERT INTO a VALUES (1, 10);",
+                "INSERT INTO a VALUES (2, 20);",
+                "INSERT INTO b VALUES (2, 200);",
+                "INSERT INTO b VALUES (3, 300);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT a.* FROM a JOIN b ON a.id = b.id;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[2, 20])])))
+            );
+            assert_eq!(
+                engine.execute("SELECT b.* FROM a JOIN b ON a.id = b.id;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[2, 200])])))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM a JOIN b ON a.id = b.id;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[
+                    2, 20, 2, 200
+                ])])))
+            );
+        }
+
+        fn engine_with_owners_and_pets() -> Engine {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE owners (id INT);",
+                "CREATE TABLE pets (id INT, owner_id INT);",
+                "INSERT INTO owners VALUES (1);",
+                "INSERT INTO owners VALUES (2);",
+                "INSERT INTO pets VALUES (10, 1);",
+                "INSERT INTO pets VALUES (20, 3);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+            engine
+        }
+
+        #[test]
+        fn left_join_without_match() {
+            let mut engine = engine_with_owners_and_pets();
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT owners.id, pets.id FROM owners LEFT JOIN pets ON owners.id = pets.owner_id;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 10]),
+                    vec![Type::Int(BigInt::from(2)), Type::Null]
+                ])))
+            );
+        }
+
+        #[test]
+        fn right_join_without_match() {
+            let mut engine = engine_with_owners_and_pets();
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT owners.id, pets.id FROM owners RIGHT JOIN pets ON owners.id = pets.owner_id;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 10]),
+                    vec![Type::Null, Type::Int(BigInt::from(20))]
+                ])))
+            );
+        }
+
+        #[test]
+        fn full_join_with_partial_overlap() {
+            let mut engine = engine_with_owners_and_pets();
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT owners.id, pets.id FROM owners FULL OUTER JOIN pets ON owners.id = pets.owner_id;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 10]),
+                    vec![Type::Int(BigInt::from(2)), Type::Null],
+                    vec![Type::Null, Type::Int(BigInt::from(20))]
+                ])))
+            );
+        }
+
+        #[test]
+        fn join_exceeding_scan_limit() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE a (id INT);",
+                "CREATE TABLE b (id INT);",
+                "INSERT INTO a VALUES (1);",
+                "INSERT INTO a VALUES (2);",
+                "INSERT INTO a VALUES (3);",
+                "INSERT INTO b VALUES (1);",
+                "INSERT INTO b VALUES (2);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+            engine.set_max_scanned_rows(10);
+
+            assert_eq!(
+                engine.execute("SELECT a.id FROM a JOIN b ON a.id = b.id;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT a.id FROM a CROSS JOIN b CROSS JOIN a AS c;".to_owned()),
+                Err(ErrorEvent::ScanLimitExceeded(10))
+            );
+        }
+
+        #[ignore] // TODO "SELECT DISTINCT ON (...)" is not supported by sqlparser
+        #[test]
+        fn select_distinct_on_column() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, category INT);",
+                "INSERT INTO pairs VALUES (1, 10);",
+                "INSERT INTO pairs VALUES (2, 20);",
+                "INSERT INTO pairs VALUES (3, 10);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute(
+                    "SELECT DISTINCT ON (category) id, category FROM pairs ORDER BY category;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[1, 10]),
+                    ints(&[2, 20])
+                ])))
+            );
+        }
+
+        #[ignore] // TODO "SELECT TOP n" is not supported by sqlparser
+        #[test]
+        fn select_top() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            assert_eq!(
+                engine.execute(format!("SELECT TOP 2 {} FROM {};", COLUMN_NAME, TABLE_NAME)),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+        }
+
+        #[test]
+        fn select_with_limit() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=4 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} LIMIT 2;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} LIMIT 10;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(
+                    (1..=4).map(int).collect::<Vec<_>>()
+                )))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} LIMIT 0;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
+            );
+        }
+
+        #[test]
+        fn select_with_offset() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=4 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} OFFSET 3;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(4)])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} OFFSET 5;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
+            );
+        }
+
+        #[test]
+        fn select_with_limit_and_offset() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=4 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} LIMIT 2 OFFSET 1;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2), int(3)])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} ORDER BY {} DESC LIMIT 2 OFFSET 1;",
+                    COLUMN_NAME, TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(3), int(2)])))
+            );
+        }
+
+        #[ignore] // TODO "TABLESAMPLE" is not supported by sqlparser
+        #[test]
+        fn select_with_tablesample() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=200 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            let sample = |engine: &mut Engine| match engine.execute(format!(
+                "SELECT {} FROM {} TABLESAMPLE BERNOULLI (10) REPEATABLE (42);",
+                COLUMN_NAME, TABLE_NAME
+            )) {
+                Ok(EngineEvent::RecordsSelected(records)) => records,
+                result => panic!("unexpected result {:?}", result),
+            };
+            let records = sample(&mut engine);
+            assert!(
+                (5..=40).contains(&records.len()),
+                "{} rows sampled",
+                records.len()
+            );
+            assert_eq!(sample(&mut engine), records);
+        }
+
+        #[ignore] // TODO "LIMIT offset, count" is not supported by sqlparser
+        #[test]
+        fn select_limit_with_offset_and_count() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {} FROM {} LIMIT 1, 2;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                engine.execute(format!(
+                    "SELECT {} FROM {} LIMIT 2 OFFSET 1;",
+                    COLUMN_NAME, TABLE_NAME
+                ))
+            );
+        }
+
+        #[test]
+        fn deeply_nested_predicate() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            assert_eq!(
+                insert_value(&mut engine, 1),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+
+            let nested = |depth: usize| {
+                format!(
+                    "SELECT {0} FROM {1} WHERE {2}{0} = 1{3};",
+                    COLUMN_NAME,
+                    TABLE_NAME,
+                    "NOT (NOT (".repeat(depth / 2),
+                    "))".repeat(depth / 2)
+                )
+            };
+
+            assert_eq!(
+                engine.execute(nested(100)),
+                Err(ErrorEvent::ExpressionTooComplex(64))
+            );
+
+            engine.set_max_expression_depth(8);
+            assert_eq!(
+                engine.execute(format!(
+                    "DELETE FROM {0} WHERE (((({1} = 1 OR {1} = 2) OR {1} = 3) OR {1} = 4) OR {1} = 5);",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Err(ErrorEvent::ExpressionTooComplex(8))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} = 1 OR {0} = 2;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
+        }
+
+        #[test]
+        fn parameterized_table_name() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.execute("CREATE TABLE notes (text VARCHAR);".to_owned()),
+                Ok(EngineEvent::TableCreated("notes".to_owned()))
+            );
+
+            assert_eq!(
+                engine.execute("SELECT * FROM ?;".to_owned()),
+                Err(ErrorEvent::InvalidParameterPosition("?".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO $1 VALUES ('text');".to_owned()),
+                Err(ErrorEvent::InvalidParameterPosition("$1".to_owned()))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO notes VALUES ('from ?');".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+        }
+
+        #[test]
+        fn boolean_columns() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE tasks (id INT, done BOOLEAN);",
+                "INSERT INTO tasks VALUES (1, true);",
+                "INSERT INTO tasks VALUES (2, false);",
+                "INSERT INTO tasks VALUES (3, TRUE);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT done FROM tasks;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::Bool(true)],
+                    vec![Type::Bool(false)],
+                    vec![Type::Bool(true)]
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM tasks WHERE done = true;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM tasks WHERE done = true AND id > 1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM tasks WHERE done;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO tasks VALUES (4, NULL);".to_owned()),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM tasks WHERE done;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO tasks VALUES (4, 1);".to_owned()),
+                Err(ErrorEvent::TypeMismatch(
+                    "column done of type boolean can't hold 1".to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn null_values() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE people (id INT PRIMARY KEY, age INT);",
+                "INSERT INTO people VALUES (1, 30);",
+                "INSERT INTO people VALUES (2, NULL);",
+                "INSERT INTO people VALUES (3, 40);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM people WHERE age = NULL;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM people WHERE age <> 30;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id, age FROM people WHERE age IS NULL;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Int(BigInt::from(2)),
+                    Type::Null
+                ]])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM people WHERE age IS NOT NULL;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM people WHERE id = NULL;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
+            );
+            assert_eq!(
+                engine.execute("SELECT COUNT(*), SUM(age), AVG(age) FROM people;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[3, 70, 35])])))
+            );
+            assert_eq!(
+                engine.execute("INSERT INTO people VALUES (NULL, 50);".to_owned()),
+                Err(ErrorEvent::TypeMismatch(
+                    "key column id can't hold NULL".to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn select_like() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE users (id INT, name VARCHAR(20));",
+                "INSERT INTO users VALUES (1, 'alice');",
+                "INSERT INTO users VALUES (2, 'bob');",
+                "INSERT INTO users VALUES (3, 'alina');",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            assert_eq!(
+                engine.execute("SELECT id FROM users WHERE name LIKE 'al%';".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM users WHERE name NOT LIKE '%i_e';".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2), int(3)])))
+            );
+        }
+
+        #[test]
+        fn row_count() {
+            let mut engine = Engine::default();
+            assert_eq!(
+                engine.row_count(TABLE_NAME),
+                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
+            );
+            create_table(&mut engine);
+            assert_eq!(engine.row_count(TABLE_NAME), Ok(0));
+
+            for value in 1..=3 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+            assert_eq!(engine.row_count(TABLE_NAME), Ok(3));
 
-    #[cfg(test)]
-    mod data_definition_language {
-        use super::*;
+            assert_eq!(
+                engine.execute(format!(
+                    "DELETE FROM {} WHERE {} = 2;",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::RecordsDeleted(1))
+            );
+            assert_eq!(engine.row_count(TABLE_NAME), Ok(2));
+        }
 
         #[test]
-        fn create_two_tables() {
+        fn explain_delete_with_range() {
             let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
 
             assert_eq!(
-                engine.execute(
-                    "CREATE TABLE simple_table (\n\
-              int_column INT,\n\
-          );"
-                    .to_owned()
-                ),
-                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+                engine.execute(format!(
+                    "EXPLAIN DELETE FROM {} WHERE {} >= 4;",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::QueryPlan(format!(
+                    "delete {}: rows=2\n",
+                    TABLE_NAME
+                )))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "EXPLAIN UPDATE {} SET {} = 0 WHERE {} = 3;",
+                    TABLE_NAME, COLUMN_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::QueryPlan(format!(
+                    "update {}: rows=1\n",
+                    TABLE_NAME
+                )))
             );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(
+                    (1..=5).map(int).collect::<Vec<_>>()
+                )))
+            );
+        }
+
+        #[test]
+        fn delete_with_range() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
 
             assert_eq!(
-                engine.execute(
-                    "CREATE TABLE another_table (\n\
-              int_column INT,\n\
-          );"
-                    .to_owned()
-                ),
-                Ok(EngineEvent::TableCreated("another_table".to_owned()))
+                engine.execute(format!(
+                    "DELETE FROM {} WHERE {} >= 4;",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::RecordsDeleted(2))
+            );
+            assert_eq!(
+                select_all(&mut engine),
+                Ok(EngineEvent::RecordsSelected(rows(
+                    (1..=3).map(int).collect::<Vec<_>>()
+                )))
             );
         }
 
         #[test]
-        fn error_when_trying_to_create_table_with_existing_name() {
+        fn explain_analyze_reports_row_counts() {
             let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE a (id INT, x INT);",
+                "CREATE TABLE b (id INT, y INT);",
+                "INSERT INTO a VALUES (1, 10);",
+                "INSERT INTO a VALUES (2, 20);",
+                "INSERT INTO b VALUES (1, 100);",
+                "INSERT INTO b VALUES (2, 200);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
+            let plan = match engine
+                .execute("EXPLAIN ANALYZE SELECT a.x, b.y FROM a, b WHERE a.id = b.id;".to_owned())
+            {
+                Ok(EngineEvent::QueryPlan(plan)) => plan,
+                other => panic!("unexpected {:?}", other),
+            };
+            let stages = plan
+                .lines()
+                .map(|line| line.split(" time=").next().unwrap())
+                .collect::<Vec<&str>>();
             assert_eq!(
-                engine.execute(
-                    "CREATE TABLE simple_table (\n\
-            int_column INT,\n\
-          );"
-                    .to_owned()
-                ),
-                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+                stages,
+                vec!["scan: rows=4", "filter: rows=2", "projection: rows=2"]
             );
+        }
+
+        #[test]
+        fn correlated_scalar_subquery() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (k INT, x INT);",
+                "CREATE TABLE other (k INT, y INT);",
+                "INSERT INTO t VALUES (1, 10);",
+                "INSERT INTO t VALUES (2, 25);",
+                "INSERT INTO t VALUES (3, 30);",
+                "INSERT INTO other VALUES (1, 10);",
+                "INSERT INTO other VALUES (2, 20);",
+                "INSERT INTO other VALUES (3, 30);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
                 engine.execute(
-                    "CREATE TABLE simple_table (\n\
-            int_column INT,\n\
-          );"
-                    .to_owned()
+                    "SELECT k FROM t WHERE x = (SELECT y FROM other WHERE other.k = t.k);"
+                        .to_owned()
                 ),
-                Err(ErrorEvent::TableAlreadyExists("simple_table".to_owned()))
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
+            );
+            assert_eq!(
+                engine.execute("SELECT k FROM t WHERE x = (SELECT y FROM other);".to_owned()),
+                Err(ErrorEvent::SubqueryReturnedMultipleRows(
+                    "SELECT y FROM other".to_owned()
+                ))
             );
         }
 
-        #[ignore] // TODO "CREATE TABLE IF NOT EXISTS" is not supported by sqlparser
         #[test]
-        fn create_table_if_not_exists() {
+        fn correlated_scalar_subquery_without_match() {
             let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE t (k INT, x INT);",
+                "CREATE TABLE other (k INT, y INT);",
+                "INSERT INTO t VALUES (1, 10);",
+                "INSERT INTO t VALUES (2, 20);",
+                "INSERT INTO other VALUES (1, 10);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
                 engine.execute(
-                    "CREATE TABLE IF NOT EXISTS simple_table (\n\
-            int_column INT,\n\
-          );"
-                    .to_owned()
+                    "SELECT k FROM t WHERE x = (SELECT y FROM other WHERE other.k = t.k);"
+                        .to_owned()
                 ),
-                Ok(EngineEvent::TableCreated("simple_table".to_owned()))
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
             );
         }
-    }
-
-    #[cfg(test)]
-    mod data_manipulation_language {
-        use std::fmt::Display;
-
-        use num_bigint::BigInt;
 
-        use super::*;
-
-        const TABLE_NAME: &'static str = "simple_table";
-        const COLUMN_NAME: &'static str = "int_column";
+        #[test]
+        fn count_all_records() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
 
-        #[allow(unused_must_use)]
-        fn create_table(engine: &mut Engine) {
-            engine.execute(format!(
-                "CREATE TABLE {} ({} INT);",
-                TABLE_NAME, COLUMN_NAME
-            ));
-        }
+            assert_eq!(
+                engine.execute(format!("SELECT COUNT(*) FROM {};", TABLE_NAME)),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(0)])))
+            );
 
-        fn insert_value<V: Display>(engine: &mut Engine, value: V) -> ExecutionResult {
-            engine.execute(format!("INSERT INTO {} VALUES ({});", TABLE_NAME, value))
-        }
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
 
-        fn select_value<V: Display>(engine: &mut Engine, value: V) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} = {2};",
-                COLUMN_NAME, TABLE_NAME, value
-            ))
+            assert_eq!(
+                engine.execute(format!("SELECT COUNT(*) FROM {};", TABLE_NAME)),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(5)])))
+            );
         }
 
-        fn select_all(engine: &mut Engine) -> ExecutionResult {
-            engine.execute(format!("SELECT {} FROM {};", COLUMN_NAME, TABLE_NAME))
-        }
+        #[test]
+        fn count_filtered_records() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
 
-        fn select_between<V: Display>(engine: &mut Engine, from: V, to: V) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} BETWEEN {2} AND {3}",
-                COLUMN_NAME, TABLE_NAME, from, to
-            ))
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT COUNT(*) FROM {} WHERE {} > 2;",
+                    TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(3)])))
+            );
         }
 
-        fn select_not_between<V: Display>(engine: &mut Engine, from: V, to: V) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} NOT BETWEEN {2} AND {3}",
-                COLUMN_NAME, TABLE_NAME, from, to
-            ))
-        }
+        #[test]
+        fn integer_aggregates() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
 
-        fn select_in<V: Display>(engine: &mut Engine, one: V, two: V, three: V) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} IN ({2}, {3}, {4});",
-                COLUMN_NAME, TABLE_NAME, one, two, three
-            ))
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT SUM({0}), MIN({0}), MAX({0}), AVG({0}) FROM {1};",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![ints(&[
+                    15, 1, 5, 3
+                ])])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT AVG({}) FROM {} WHERE {} < 3;",
+                    COLUMN_NAME, TABLE_NAME, COLUMN_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
+            );
         }
 
-        fn select_not_in<V: Display>(
-            engine: &mut Engine,
-            one: V,
-            two: V,
-            three: V,
-        ) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} NOT IN ({2}, {3}, {4});",
-                COLUMN_NAME, TABLE_NAME, one, two, three
-            ))
-        }
+        #[test]
+        fn integer_aggregates_of_empty_table() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
 
-        fn select_with_and<V: Display>(engine: &mut Engine, one: V, two: V) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} = {2} AND {0} = {3};",
-                COLUMN_NAME, TABLE_NAME, one, two
-            ))
+            assert_eq!(
+                engine.execute(format!("SELECT SUM({}) FROM {};", COLUMN_NAME, TABLE_NAME)),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(0)])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT MIN({0}), MAX({0}), AVG({0}) FROM {1};",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Null,
+                    Type::Null,
+                    Type::Null
+                ]])))
+            );
         }
 
-        fn select_with_or<V: Display>(engine: &mut Engine, one: V, two: V) -> ExecutionResult {
-            engine.execute(format!(
-                "SELECT {0} FROM {1} WHERE {0} = {2} OR {0} = {3};",
-                COLUMN_NAME, TABLE_NAME, one, two
-            ))
-        }
+        #[test]
+        fn boolean_aggregates() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE tasks (id INT, project INT, done BOOLEAN);",
+                "INSERT INTO tasks VALUES (1, 1, true);",
+                "INSERT INTO tasks VALUES (2, 1, true);",
+                "INSERT INTO tasks VALUES (3, 2, false);",
+                "INSERT INTO tasks VALUES (4, 2, true);",
+                "INSERT INTO tasks VALUES (5, 3, false);",
+                "INSERT INTO tasks VALUES (6, 3, NULL);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
-        fn update_value<V: Display>(engine: &mut Engine, from: V, to: V) -> ExecutionResult {
-            engine.execute(format!(
-                "UPDATE {0} SET {1} = {2} where {1} = {3}",
-                TABLE_NAME, COLUMN_NAME, from, to
-            ))
+            assert_eq!(
+                engine.execute("SELECT BOOL_AND(done), BOOL_OR(done) FROM tasks;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Bool(false),
+                    Type::Bool(true)
+                ]])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "SELECT project, BOOL_AND(done), BOOL_OR(done) FROM tasks GROUP BY project;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![
+                        Type::Int(BigInt::from(1)),
+                        Type::Bool(true),
+                        Type::Bool(true)
+                    ],
+                    vec![
+                        Type::Int(BigInt::from(2)),
+                        Type::Bool(false),
+                        Type::Bool(true)
+                    ],
+                    vec![
+                        Type::Int(BigInt::from(3)),
+                        Type::Bool(false),
+                        Type::Bool(false)
+                    ],
+                ])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "SELECT BOOL_AND(done), BOOL_OR(done) FROM tasks WHERE id > 6;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::Null,
+                    Type::Null
+                ]])))
+            );
         }
 
-        fn update_all(engine: &mut Engine) -> ExecutionResult {
-            engine.execute(format!("UPDATE {0} SET {1} = 100", TABLE_NAME, COLUMN_NAME))
-        }
+        #[test]
+        fn count_with_ungrouped_column() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
 
-        fn delete_value<V: Display>(engine: &mut Engine, value: V) -> ExecutionResult {
-            engine.execute(format!(
-                "DELETE FROM {} WHERE {} = {}",
-                TABLE_NAME, COLUMN_NAME, value
-            ))
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {}, COUNT(*) FROM {};",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Err(ErrorEvent::ColumnNotGrouped(COLUMN_NAME.to_owned()))
+            );
         }
 
-        fn delete_all(engine: &mut Engine) -> ExecutionResult {
-            engine.execute(format!("DELETE FROM {}", TABLE_NAME))
-        }
+        #[test]
+        fn select_distinct() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE visits (id INT, page VARCHAR(10), status INT);",
+                "INSERT INTO visits VALUES (1, 'home', 200);",
+                "INSERT INTO visits VALUES (2, 'about', 200);",
+                "INSERT INTO visits VALUES (3, 'home', 200);",
+                "INSERT INTO visits VALUES (4, 'home', 404);",
+                "INSERT INTO visits VALUES (5, 'about', 200);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
-        fn int(val: i32) -> Vec<u8> {
-            bincode::serialize(&BigInt::from(val)).unwrap()
+            let row = |page: &str, status: i32| {
+                vec![
+                    Type::VarChar(page.to_owned()),
+                    Type::Int(BigInt::from(status)),
+                ]
+            };
+            assert_eq!(
+                engine.execute("SELECT DISTINCT page FROM visits;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    vec![Type::VarChar("home".to_owned())],
+                    vec![Type::VarChar("about".to_owned())]
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT DISTINCT page, status FROM visits;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    row("home", 200),
+                    row("about", 200),
+                    row("home", 404)
+                ])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "SELECT DISTINCT page, status FROM visits ORDER BY status DESC;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    row("home", 404),
+                    row("home", 200),
+                    row("about", 200)
+                ])))
+            );
         }
 
         #[test]
-        fn insert_into_not_existed_table() {
+        fn group_by_multiple_columns() {
             let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE sales (id INT, region VARCHAR(10), year INT);",
+                "INSERT INTO sales VALUES (1, 'eu', 2019);",
+                "INSERT INTO sales VALUES (2, 'us', 2019);",
+                "INSERT INTO sales VALUES (3, 'eu', 2019);",
+                "INSERT INTO sales VALUES (4, 'eu', 2020);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
+            let row = |region: &str, year: i32| {
+                vec![
+                    Type::VarChar(region.to_owned()),
+                    Type::Int(BigInt::from(year)),
+                ]
+            };
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
-            )
+                engine.execute("SELECT region, year FROM sales GROUP BY region, year;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    row("eu", 2019),
+                    row("eu", 2020),
+                    row("us", 2019)
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id, region FROM sales GROUP BY region, year;".to_owned()),
+                Err(ErrorEvent::ColumnNotGrouped("id".to_owned()))
+            );
         }
 
         #[test]
-        fn select_from_not_existed_table() {
+        fn group_by_with_aggregates() {
             let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE sales (id INT, region VARCHAR(10), amount INT);",
+                "INSERT INTO sales VALUES (1, 'us', 10);",
+                "INSERT INTO sales VALUES (2, 'eu', 20);",
+                "INSERT INTO sales VALUES (3, 'us', 30);",
+                "INSERT INTO sales VALUES (4, 'eu', 5);",
+                "INSERT INTO sales VALUES (5, 'us', 2);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
+            let row = |region: &str, count: i32, total: i32| {
+                vec![
+                    Type::VarChar(region.to_owned()),
+                    Type::Int(BigInt::from(count)),
+                    Type::Int(BigInt::from(total)),
+                ]
+            };
             assert_eq!(
-                select_all(&mut engine),
-                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
-            )
+                engine.execute(
+                    "SELECT region, COUNT(*), SUM(amount) FROM sales GROUP BY region;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    row("eu", 2, 25),
+                    row("us", 3, 42)
+                ])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "SELECT region, COUNT(*), MAX(amount) FROM sales WHERE amount > 5 GROUP BY region;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    row("eu", 1, 20),
+                    row("us", 2, 30)
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT amount, COUNT(*) FROM sales GROUP BY region;".to_owned()),
+                Err(ErrorEvent::ColumnNotGrouped("amount".to_owned()))
+            );
         }
 
         #[test]
-        fn update_from_not_existed_table() {
+        fn group_by_having() {
             let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE sales (id INT, region VARCHAR(10), amount INT);",
+                "INSERT INTO sales VALUES (1, 'us', 10);",
+                "INSERT INTO sales VALUES (2, 'eu', 20);",
+                "INSERT INTO sales VALUES (3, 'us', 30);",
+                "INSERT INTO sales VALUES (4, 'eu', 5);",
+                "INSERT INTO sales VALUES (5, 'us', 2);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
-                update_all(&mut engine),
-                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
-            )
+                engine.execute(
+                    "SELECT region, COUNT(*) FROM sales GROUP BY region HAVING COUNT(*) > 2;"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![
+                    Type::VarChar("us".to_owned()),
+                    Type::Int(BigInt::from(3))
+                ]])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "SELECT region FROM sales GROUP BY region HAVING SUM(amount) < 30 AND region <> 'us';"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(rows(vec![vec![Type::VarChar(
+                    "eu".to_owned()
+                )]])))
+            );
+            assert_eq!(
+                engine.execute(
+                    "SELECT region FROM sales GROUP BY region HAVING COUNT(*) > 5;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsSelected(vec![]))
+            );
+            assert_eq!(
+                engine.execute("SELECT COUNT(*) FROM sales HAVING MAX(amount) > 100;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(vec![]))
+            );
         }
 
         #[test]
-        fn delete_from_not_existed_table() {
+        fn select_less_and_greater_than() {
             let mut engine = Engine::default();
+            create_table(&mut engine);
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
 
             assert_eq!(
-                delete_all(&mut engine),
-                Err(ErrorEvent::TableDoesNotExist(TABLE_NAME.to_owned()))
-            )
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} < 3;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} > 3;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(4), int(5)])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} > 5;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
+            );
         }
 
         #[test]
-        fn insert_select_single_record() {
+        fn select_less_and_greater_than_or_equal() {
             let mut engine = Engine::default();
             create_table(&mut engine);
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} <= 3;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
             );
 
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
             assert_eq!(
-                select_value(&mut engine, 1),
-                Ok(EngineEvent::RecordsSelected(vec![int(1)]))
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} <= 3;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(2),
+                    int(3)
+                ])))
+            );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} >= 3;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(3),
+                    int(4),
+                    int(5)
+                ])))
             );
         }
 
         #[test]
-        fn insert_many_select_single_record() {
+        fn select_not_equal() {
             let mut engine = Engine::default();
             create_table(&mut engine);
 
+            for value in 1..=5 {
+                assert_eq!(
+                    insert_value(&mut engine, value),
+                    Ok(EngineEvent::RecordsInserted(1))
+                );
+            }
+
+            for operator in &["!=", "<>"] {
+                assert_eq!(
+                    engine.execute(format!(
+                        "SELECT {0} FROM {1} WHERE {0} {2} 3;",
+                        COLUMN_NAME, TABLE_NAME, operator
+                    )),
+                    Ok(EngineEvent::RecordsSelected(rows(vec![
+                        int(1),
+                        int(2),
+                        int(4),
+                        int(5)
+                    ])))
+                );
+            }
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
-            );
-            assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
-            );
-            assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} != 6;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(
+                    (1..=5).map(int).collect::<Vec<_>>()
+                )))
             );
+        }
+
+        #[test]
+        fn select_less_than_of_non_key_column() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, value INT);",
+                "INSERT INTO pairs VALUES (1, 30);",
+                "INSERT INTO pairs VALUES (2, 10);",
+                "INSERT INTO pairs VALUES (3, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
-                select_value(&mut engine, 2),
-                Ok(EngineEvent::RecordsSelected(vec![int(2)]))
+                engine.execute("SELECT id FROM pairs WHERE value < 30;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2), int(3)])))
             );
         }
 
         #[test]
-        fn insert_many_select_all_records() {
+        fn select_by_conditions_other_than_key_range() {
             let mut engine = Engine::default();
-            create_table(&mut engine);
+            for statement in &[
+                "CREATE TABLE t (a INT, b INT);",
+                "INSERT INTO t VALUES (-1, 10);",
+                "INSERT INTO t VALUES (1, 20);",
+                "INSERT INTO t VALUES (2, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT a FROM t WHERE (b = 20);".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(2)])))
             );
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT b FROM t WHERE a = 1.0;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(20)])))
             );
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT b FROM t WHERE a = -1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(10)])))
             );
-
             assert_eq!(
-                select_all(&mut engine),
-                Ok(EngineEvent::RecordsSelected(vec![int(1), int(2), int(3)]))
+                engine.execute("SELECT b FROM t WHERE a BETWEEN 2 AND 1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(vec![]))
             );
         }
 
         #[test]
-        fn update_single_value() {
+        fn compare_decimal_column_with_integer() {
             let mut engine = Engine::default();
-            create_table(&mut engine);
+            for statement in &[
+                "CREATE TABLE prices (id INT, price DECIMAL(10, 2));",
+                "INSERT INTO prices VALUES (1, 2.5);",
+                "INSERT INTO prices VALUES (2, 10.5);",
+                "INSERT INTO prices VALUES (3, 3);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
-            );
-            assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT id FROM prices WHERE price > 3;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(2)])))
             );
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT id FROM prices WHERE price < 3;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1)])))
             );
-
             assert_eq!(
-                update_value(&mut engine, 4, 2),
-                Ok(EngineEvent::RecordsUpdated)
+                engine.execute("SELECT id FROM prices WHERE price = 3;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(3)])))
             );
             assert_eq!(
-                select_all(&mut engine),
-                Ok(EngineEvent::RecordsSelected(vec![int(1), int(4), int(3)]))
+                engine.execute("SELECT id FROM prices ORDER BY price;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(3),
+                    int(2)
+                ])))
             );
         }
 
         #[test]
-        fn update_all_values() {
+        fn order_by_computed_alias() {
             let mut engine = Engine::default();
-            create_table(&mut engine);
+            for statement in &[
+                "CREATE TABLE pairs (id INT, value INT);",
+                "INSERT INTO pairs VALUES (1, 30);",
+                "INSERT INTO pairs VALUES (2, 10);",
+                "INSERT INTO pairs VALUES (3, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT id, value + 1 AS n FROM pairs ORDER BY n;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    ints(&[2, 11]),
+                    ints(&[3, 21]),
+                    ints(&[1, 31])
+                ])))
             );
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT id FROM pairs ORDER BY value * -1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(3),
+                    int(2)
+                ])))
             );
+        }
+
+        #[test]
+        fn order_by_numeric_value() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE pairs (id INT, value INT);",
+                "INSERT INTO pairs VALUES (1, 100);",
+                "INSERT INTO pairs VALUES (2, 9);",
+                "INSERT INTO pairs VALUES (3, 10);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT value FROM pairs ORDER BY value ASC;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(9),
+                    int(10),
+                    int(100)
+                ])))
             );
-
-            assert_eq!(update_all(&mut engine), Ok(EngineEvent::RecordsUpdated));
             assert_eq!(
-                select_all(&mut engine),
-                Ok(EngineEvent::RecordsSelected(vec![
-                    int(100),
+                engine.execute("SELECT value FROM pairs ORDER BY value DESC;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
                     int(100),
-                    int(100)
-                ]))
+                    int(10),
+                    int(9)
+                ])))
+            );
+            assert_eq!(
+                engine.execute("SELECT id FROM pairs ORDER BY value;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(2),
+                    int(3),
+                    int(1)
+                ])))
             );
         }
 
         #[test]
-        fn delete_single_value() {
+        fn order_by_ties_in_key_order() {
             let mut engine = Engine::default();
-            create_table(&mut engine);
+            for statement in &[
+                "CREATE TABLE pairs (id INT, value INT);",
+                "INSERT INTO pairs VALUES (4, 10);",
+                "INSERT INTO pairs VALUES (2, 20);",
+                "INSERT INTO pairs VALUES (3, 10);",
+                "INSERT INTO pairs VALUES (1, 20);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT id FROM pairs ORDER BY value;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(3),
+                    int(4),
+                    int(1),
+                    int(2)
+                ])))
             );
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT id FROM pairs ORDER BY value DESC;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(1),
+                    int(2),
+                    int(3),
+                    int(4)
+                ])))
             );
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine
+                    .execute("SELECT id FROM pairs GROUP BY value, id ORDER BY value;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    int(3),
+                    int(4),
+                    int(1),
+                    int(2)
+                ])))
             );
+        }
+
+        #[test]
+        fn order_by_nonexistent_column() {
+            let mut engine = Engine::default();
+            create_table(&mut engine);
 
             assert_eq!(
-                delete_value(&mut engine, 2),
-                Ok(EngineEvent::RecordsDeleted)
+                engine.execute(format!(
+                    "SELECT {} FROM {} ORDER BY nonexistent;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Err(ErrorEvent::ColumnDoesNotExist("nonexistent".to_owned()))
             );
             assert_eq!(
-                select_all(&mut engine),
-                Ok(EngineEvent::RecordsSelected(vec![int(1), int(3)]))
+                engine.execute("VALUES (1), (2) ORDER BY nonexistent;".to_owned()),
+                Err(ErrorEvent::ColumnDoesNotExist("nonexistent".to_owned()))
             );
         }
 
         #[test]
-        fn delete_all_values() {
+        fn select_with_and_predicate() {
             let mut engine = Engine::default();
             create_table(&mut engine);
 
             assert_eq!(
                 insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
             assert_eq!(
                 insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
             assert_eq!(
                 insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 4),
+                Ok(EngineEvent::RecordsInserted(1))
+            );
+            assert_eq!(
+                insert_value(&mut engine, 5),
+                Ok(EngineEvent::RecordsInserted(1))
             );
 
-            assert_eq!(delete_all(&mut engine), Ok(EngineEvent::RecordsDeleted));
             assert_eq!(
-                select_all(&mut engine),
-                Ok(EngineEvent::RecordsSelected(vec![]))
+                select_with_and(&mut engine, 1, 3),
+                Ok(EngineEvent::RecordsSelected(rows(vec![])))
             );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} > 2 AND {0} < 5;",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(3), int(4)])))
+            )
         }
 
         #[test]
-        fn select_in_range() {
+        fn select_with_or_predicate() {
             let mut engine = Engine::default();
             create_table(&mut engine);
 
             assert_eq!(
                 insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
             assert_eq!(
                 insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
             assert_eq!(
                 insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
             assert_eq!(
                 insert_value(&mut engine, 4),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
             assert_eq!(
                 insert_value(&mut engine, 5),
-                Ok(EngineEvent::RecordInserted)
+                Ok(EngineEvent::RecordsInserted(1))
             );
 
             assert_eq!(
-                select_between(&mut engine, 2, 4),
-                Ok(EngineEvent::RecordsSelected(vec![int(2), int(3), int(4)]))
+                select_with_or(&mut engine, 1, 3),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(3)])))
             );
+            assert_eq!(
+                engine.execute(format!(
+                    "SELECT {0} FROM {1} WHERE {0} = 1 OR ({0} > 3 AND {0} < 5);",
+                    COLUMN_NAME, TABLE_NAME
+                )),
+                Ok(EngineEvent::RecordsSelected(rows(vec![int(1), int(4)])))
+            )
         }
+    }
+    #[cfg(test)]
+    mod collation {
+        use super::*;
 
-        #[test]
-        fn select_out_range() {
-            let mut engine = Engine::default();
-            create_table(&mut engine);
+        fn string(value: &str) -> Type {
+            Type::VarChar(value.to_owned())
+        }
 
+        #[test]
+        fn binary() {
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                Collation::Binary.compare(&string("B"), &string("a")),
+                Ordering::Less
             );
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                Collation::Binary.compare(&string("a"), &string("A")),
+                Ordering::Greater
             );
+        }
+
+        #[test]
+        fn case_insensitive() {
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                Collation::CaseInsensitive.compare(&string("B"), &string("a")),
+                Ordering::Greater
             );
             assert_eq!(
-                insert_value(&mut engine, 4),
-                Ok(EngineEvent::RecordInserted)
+                Collation::CaseInsensitive.compare(&string("a"), &string("A")),
+                Ordering::Equal
             );
+        }
+    }
+
+    mod rows {
+        use super::*;
+
+        fn engine_with_users() -> Engine {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE users (id INT, name VARCHAR(20), score DECIMAL(10, 2));",
+                "INSERT INTO users VALUES (1, 'alice', 9.5);",
+                "INSERT INTO users VALUES (2, 'bob', 7.25);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+            engine
+        }
+
+        #[test]
+        fn count_as_i64() {
+            let mut engine = engine_with_users();
+            let rows = engine
+                .select_rows("SELECT COUNT(*) FROM users;".to_owned())
+                .expect("rows are selected");
+
+            let count: i64 = rows
+                .iter()
+                .next()
+                .expect("count row")
+                .get_i64(0)
+                .expect("count is an i64");
+            assert_eq!(count, 2);
+        }
+
+        #[test]
+        fn typed_accessors() {
+            let mut engine = engine_with_users();
+            let rows = engine
+                .select_rows("SELECT id, name, score FROM users;".to_owned())
+                .expect("rows are selected");
+
+            assert_eq!(rows.columns(), ["id", "name", "score"]);
+            assert_eq!(rows.len(), 2);
+            let row = rows.iter().nth(1).expect("second row");
+            assert_eq!(row.get_int(0), Ok(&BigInt::from(2)));
+            assert_eq!(row.get_str(1), Ok("bob"));
             assert_eq!(
-                insert_value(&mut engine, 5),
-                Ok(EngineEvent::RecordInserted)
+                row.get_decimal(2),
+                Ok(&"7.25".parse::<BigDecimal>().unwrap())
             );
-
             assert_eq!(
-                select_not_between(&mut engine, 2, 4),
-                Ok(EngineEvent::RecordsSelected(vec![int(1), int(5)]))
+                row.get_by_name("name"),
+                Ok(&Type::VarChar("bob".to_owned()))
             );
         }
 
         #[test]
-        fn select_in_enumeration() {
-            let mut engine = Engine::default();
-            create_table(&mut engine);
+        fn selected_rows_carry_column_names() {
+            let mut engine = engine_with_users();
+            let rows = match engine.execute("SELECT name, id AS user_id FROM users;".to_owned()) {
+                Ok(EngineEvent::RecordsSelected(rows)) => rows,
+                result => panic!("unexpected result {:?}", result),
+            };
 
+            assert_eq!(rows.len(), 2);
+            assert_eq!(rows[0].columns(), ["name", "user_id"]);
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                rows[1].get_by_name("user_id"),
+                Ok(&Type::Int(BigInt::from(2)))
             );
+            assert_eq!(rows[1].get_str(0), Ok("bob"));
+        }
+
+        #[test]
+        fn type_mismatch() {
+            let mut engine = engine_with_users();
+            let rows = engine
+                .select_rows("SELECT id, name FROM users;".to_owned())
+                .expect("rows are selected");
+            let row = rows.iter().next().expect("first row");
+
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                row.get_int(1),
+                Err(ErrorEvent::TypeMismatch(
+                    "column at index 1 holds VarChar, not Int".to_owned()
+                ))
             );
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                row.get_str(0),
+                Err(ErrorEvent::TypeMismatch(
+                    "column at index 0 holds Int, not VarChar".to_owned()
+                ))
             );
+        }
+
+        #[test]
+        fn missing_columns() {
+            let mut engine = engine_with_users();
+            let rows = engine
+                .select_rows("SELECT id FROM users;".to_owned())
+                .expect("rows are selected");
+            let row = rows.iter().next().expect("first row");
+
             assert_eq!(
-                insert_value(&mut engine, 4),
-                Ok(EngineEvent::RecordInserted)
+                row.get(1),
+                Err(ErrorEvent::ColumnDoesNotExist(
+                    "column at index 1".to_owned()
+                ))
             );
             assert_eq!(
-                insert_value(&mut engine, 5),
-                Ok(EngineEvent::RecordInserted)
+                row.get_by_name("name"),
+                Err(ErrorEvent::ColumnDoesNotExist("name".to_owned()))
             );
+        }
+    }
 
-            assert_eq!(
-                select_in(&mut engine, 1, 3, 5),
-                Ok(EngineEvent::RecordsSelected(vec![int(1), int(3), int(5)]))
-            )
+    mod transactions {
+        use super::*;
+
+        fn engine_with_table() -> Engine {
+            let mut engine = Engine::default();
+            assert!(engine
+                .execute("CREATE TABLE simple_table (id INT);".to_owned())
+                .is_ok());
+            engine
+        }
+
+        fn select_all(engine: &mut Engine) -> ExecutionResult {
+            engine.execute("SELECT * FROM simple_table;".to_owned())
+        }
+
+        fn ids(values: &[i32]) -> ExecutionResult {
+            Ok(EngineEvent::RecordsSelected(rows(
+                values
+                    .iter()
+                    .map(|value| vec![Type::Int(BigInt::from(*value))])
+                    .collect(),
+            )))
         }
 
         #[test]
-        fn select_out_of_enumeration() {
-            let mut engine = Engine::default();
-            create_table(&mut engine);
+        fn rolled_back_insert() {
+            let mut engine = engine_with_table();
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("BEGIN;".to_owned()),
+                Ok(EngineEvent::TransactionStarted)
             );
+            assert!(engine
+                .execute("INSERT INTO simple_table VALUES (1);".to_owned())
+                .is_ok());
+            assert_eq!(select_all(&mut engine), ids(&[1]));
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("ROLLBACK;".to_owned()),
+                Ok(EngineEvent::TransactionRolledBack)
             );
+            assert_eq!(select_all(&mut engine), ids(&[]));
+        }
+
+        #[test]
+        fn committed_insert() {
+            let mut engine = engine_with_table();
+
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("BEGIN;".to_owned()),
+                Ok(EngineEvent::TransactionStarted)
             );
+            assert!(engine
+                .execute("INSERT INTO simple_table VALUES (1);".to_owned())
+                .is_ok());
             assert_eq!(
-                insert_value(&mut engine, 4),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("COMMIT;".to_owned()),
+                Ok(EngineEvent::TransactionCommitted)
             );
+            assert_eq!(select_all(&mut engine), ids(&[1]));
             assert_eq!(
-                insert_value(&mut engine, 5),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("ROLLBACK;".to_owned()),
+                Err(ErrorEvent::TransactionState(
+                    "there is no transaction in progress".to_owned()
+                ))
             );
+        }
+    }
 
+    mod composite_keys {
+        use super::*;
+
+        fn engine_with_table() -> Engine {
+            let mut engine = Engine::default();
             assert_eq!(
-                select_not_in(&mut engine, 1, 3, 5),
-                Ok(EngineEvent::RecordsSelected(vec![int(2), int(4)]))
-            )
+                engine.execute(
+                    "CREATE TABLE pairs (a INT, b INT, label VARCHAR(10), PRIMARY KEY (a, b));"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::TableCreated("pairs".to_owned()))
+            );
+            engine
+        }
+
+        fn pair(a: i32, b: i32, label: &str) -> Vec<Type> {
+            vec![
+                Type::Int(BigInt::from(a)),
+                Type::Int(BigInt::from(b)),
+                Type::VarChar(label.to_owned()),
+            ]
         }
 
-        #[ignore]
         #[test]
-        fn select_with_and_predicate() {
-            let mut engine = Engine::default();
-            create_table(&mut engine);
+        fn insert_rows_sharing_a_column_of_the_key() {
+            let mut engine = engine_with_table();
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute(
+                    "INSERT INTO pairs VALUES (1, 1, 'first'), (1, 2, 'second'), (2, 1, 'third');"
+                        .to_owned()
+                ),
+                Ok(EngineEvent::RecordsInserted(3))
             );
             assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT * FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    pair(1, 1, "first"),
+                    pair(1, 2, "second"),
+                    pair(2, 1, "third"),
+                ])))
             );
+        }
+
+        #[test]
+        fn update_by_every_column_of_the_key() {
+            let mut engine = engine_with_table();
+            assert!(engine
+                .execute(
+                    "INSERT INTO pairs VALUES (1, 1, 'first'), (1, 2, 'second'), (2, 1, 'third');"
+                        .to_owned()
+                )
+                .is_ok());
+
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute(
+                    "UPDATE pairs SET label = 'updated' WHERE a = 1 AND b = 2;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsUpdated(1))
             );
             assert_eq!(
-                insert_value(&mut engine, 4),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT * FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    pair(1, 1, "first"),
+                    pair(1, 2, "updated"),
+                    pair(2, 1, "third"),
+                ])))
             );
             assert_eq!(
-                insert_value(&mut engine, 5),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute(
+                    "UPDATE pairs SET label = 'missing' WHERE a = 2 AND b = 2;".to_owned()
+                ),
+                Ok(EngineEvent::RecordsUpdated(0))
             );
+        }
+
+        #[test]
+        fn select_by_every_column_of_the_key() {
+            let mut engine = engine_with_table();
+            assert!(engine
+                .execute(
+                    "INSERT INTO pairs VALUES (1, 1, 'first'), (1, 2, 'second'), (2, 1, 'third');"
+                        .to_owned()
+                )
+                .is_ok());
 
             assert_eq!(
-                select_with_and(&mut engine, 1, 3),
+                engine.execute("SELECT * FROM pairs WHERE a = 1 AND b = 2;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![pair(
+                    1, 2, "second"
+                )])))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM pairs WHERE b = 1 AND a = 2;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![pair(
+                    2, 1, "third"
+                )])))
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM pairs WHERE a = 2 AND b = 2;".to_owned()),
                 Ok(EngineEvent::RecordsSelected(vec![]))
-            )
+            );
+            assert_eq!(
+                engine.execute("SELECT * FROM pairs WHERE a = 1;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    pair(1, 1, "first"),
+                    pair(1, 2, "second"),
+                ])))
+            );
         }
 
-        #[ignore]
         #[test]
-        fn select_with_or_predicate() {
-            let mut engine = Engine::default();
-            create_table(&mut engine);
+        fn duplicate_key() {
+            let mut engine = engine_with_table();
+            assert!(engine
+                .execute("INSERT INTO pairs VALUES (1, 2, 'first');".to_owned())
+                .is_ok());
 
             assert_eq!(
-                insert_value(&mut engine, 1),
-                Ok(EngineEvent::RecordInserted)
-            );
-            assert_eq!(
-                insert_value(&mut engine, 2),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("INSERT INTO pairs VALUES (1, 2, 'second');".to_owned()),
+                Err(ErrorEvent::DuplicateKey(
+                    "pairs already has a row with key (1, 2)".to_owned()
+                ))
             );
+            assert!(engine
+                .execute("INSERT INTO pairs VALUES (2, 1, 'second');".to_owned())
+                .is_ok());
             assert_eq!(
-                insert_value(&mut engine, 3),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("UPDATE pairs SET a = 1, b = 2 WHERE label = 'second';".to_owned()),
+                Err(ErrorEvent::DuplicateKey(
+                    "pairs already has a row with key (1, 2)".to_owned()
+                ))
             );
             assert_eq!(
-                insert_value(&mut engine, 4),
-                Ok(EngineEvent::RecordInserted)
+                engine.execute("SELECT * FROM pairs;".to_owned()),
+                Ok(EngineEvent::RecordsSelected(rows(vec![
+                    pair(1, 2, "first"),
+                    pair(2, 1, "second"),
+                ])))
             );
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        #[test]
+        fn parse_error() {
+            let mut engine = Engine::default();
+
+            assert!(matches!(
+                engine.execute("SELEC id FROM simple_table;".to_owned()),
+                Err(ErrorEvent::ParseError(_))
+            ));
+        }
+
+        #[test]
+        fn unsupported_operator() {
+            let mut engine = Engine::default();
+            for statement in &[
+                "CREATE TABLE simple_table (id INT, amount INT);",
+                "INSERT INTO simple_table VALUES (1, 10);",
+            ] {
+                assert!(engine.execute((*statement).to_owned()).is_ok());
+            }
+
+            let result =
+                engine.execute("SELECT * FROM simple_table WHERE amount / 2 = 5;".to_owned());
+            assert_eq!(result, Err(ErrorEvent::UnsupportedOperator("/".to_owned())));
             assert_eq!(
-                insert_value(&mut engine, 5),
-                Ok(EngineEvent::RecordInserted)
+                result.unwrap_err().to_string(),
+                "operator / is not supported"
             );
+        }
+
+        #[test]
+        fn unsupported_statement() {
+            let mut engine = Engine::default();
 
             assert_eq!(
-                select_with_or(&mut engine, 1, 3),
-                Ok(EngineEvent::RecordsSelected(vec![int(1), int(3)]))
-            )
+                engine.execute("CREATE VIEW v AS SELECT 1;".to_owned()),
+                Err(ErrorEvent::UnsupportedStatement {
+                    kind: "CreateView".to_owned()
+                })
+            );
         }
     }
 }
@@ -1,21 +1,100 @@
+use std::cmp::Ordering;
 use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
 
 use bigdecimal::BigDecimal;
 use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use sqlparser::ast::Value;
 
 type AstTypeValue = Value;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Type {
     Int(BigInt),
     Decimal(BigDecimal),
     VarChar(String),
+    Json(JsonValue),
+    List(Vec<Type>),
+    Bool(bool),
+    /// an absent value, ordered after values of every other type
+    Null,
+}
+
+// serde_json::Value is neither ordered nor hashable, JSON values are compared by their text
+impl Ord for Type {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Type::Int(left), Type::Int(right)) => left.cmp(right),
+            (Type::Decimal(left), Type::Decimal(right)) => left.cmp(right),
+            (Type::VarChar(left), Type::VarChar(right)) => left.cmp(right),
+            (Type::Json(left), Type::Json(right)) => left.to_string().cmp(&right.to_string()),
+            (Type::List(left), Type::List(right)) => left.cmp(right),
+            (Type::Bool(left), Type::Bool(right)) => left.cmp(right),
+            (left, right) => left.rank().cmp(&right.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Type {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Type {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Type::Int(value) => value.hash(state),
+            Type::Decimal(value) => value.hash(state),
+            Type::VarChar(value) => value.hash(state),
+            Type::Json(value) => value.to_string().hash(state),
+            Type::List(values) => values.hash(state),
+            Type::Bool(value) => value.hash(state),
+            Type::Null => {}
+        }
+    }
+}
+
+/// how `Int` values are written for clients, as JSON numbers overflow on big integers
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum IntEncoding {
+    /// decimal digits in a string, e.g. `"255"`
+    #[default]
+    Decimal,
+    /// lowercase hex digits in a string, e.g. `"0xff"` or `"-0xff"`
+    Hex,
+    /// big-endian two's complement bytes, e.g. `[0, 255]`
+    Bytes,
+}
+
+/// kind of values a column holds
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TypeTag {
+    Int,
+    Decimal,
+    VarChar,
+    Json,
+    List,
+    Bool,
+    /// of NULL only, which a column of any type can hold
+    Null,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum TypeError {
     Unsupported(String),
+    Mismatch(String),
+    Encoding(String),
+    /// the text is not a valid literal of the target type
+    ParseFailure {
+        raw: String,
+        target: TypeTag,
+    },
 }
 
 impl TryFrom<AstTypeValue> for Type {
@@ -32,11 +111,369 @@ impl TryFrom<AstTypeValue> for Type {
                 }
             }
             Value::SingleQuotedString(value) => Ok(Type::VarChar(value)),
+            Value::Boolean(value) => Ok(Type::Bool(value)),
+            Value::Null => Ok(Type::Null),
+            Value::HexStringLiteral(value) => {
+                let digits = value.chars().collect::<Vec<char>>();
+                let mut bytes = vec![];
+                for pair in digits.chunks(2) {
+                    match (
+                        pair,
+                        u8::from_str_radix(&pair.iter().collect::<String>(), 16),
+                    ) {
+                        ([_, _], Ok(byte)) => bytes.push(byte),
+                        _ => {
+                            return Err(TypeError::ParseFailure {
+                                raw: format!("X'{}'", value),
+                                target: TypeTag::VarChar,
+                            })
+                        }
+                    }
+                }
+                Type::var_char(bytes)
+            }
             ast_type => Err(TypeError::Unsupported(format!("{:?}", ast_type))),
         }
     }
 }
 
+impl From<Type> for AstTypeValue {
+    // JSON and lists have no literal of their own and are written as their text
+    fn from(value: Type) -> Self {
+        match value {
+            Type::Int(value) => Value::Number(BigDecimal::from(value)),
+            Type::Decimal(value) => Value::Number(value),
+            Type::VarChar(value) => Value::SingleQuotedString(value),
+            Type::Bool(value) => Value::Boolean(value),
+            Type::Null => Value::Null,
+            Type::Json(value) => Value::SingleQuotedString(value.to_string()),
+            Type::List(values) => Value::SingleQuotedString(format!(
+                "{{{}}}",
+                values
+                    .into_iter()
+                    .map(|value| Value::from(value).to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )),
+        }
+    }
+}
+
+impl Type {
+    pub fn var_char(bytes: Vec<u8>) -> Result<Type, TypeError> {
+        String::from_utf8(bytes)
+            .map(Type::VarChar)
+            .map_err(|error| {
+                TypeError::Encoding(format!(
+                    "{:?} is not a valid UTF-8 string: {}",
+                    error.as_bytes(),
+                    error.utf8_error()
+                ))
+            })
+    }
+
+    pub fn json(text: &str) -> Result<Type, TypeError> {
+        serde_json::from_str(text).map(Type::Json).map_err(|error| {
+            TypeError::Mismatch(format!("{:?} is not a valid JSON: {}", text, error))
+        })
+    }
+
+    /// the value of the target type written as the text
+    pub fn parse(raw: &str, target: TypeTag) -> Result<Type, TypeError> {
+        let failure = || TypeError::ParseFailure {
+            raw: raw.to_owned(),
+            target,
+        };
+        match target {
+            TypeTag::Int => BigInt::from_str(raw.trim())
+                .map(Type::Int)
+                .map_err(|_| failure()),
+            TypeTag::Decimal => BigDecimal::from_str(raw.trim())
+                .map(Type::Decimal)
+                .map_err(|_| failure()),
+            TypeTag::VarChar => Ok(Type::VarChar(raw.to_owned())),
+            TypeTag::Json => serde_json::from_str(raw)
+                .map(Type::Json)
+                .map_err(|_| failure()),
+            TypeTag::Bool => match raw.trim().to_lowercase().as_str() {
+                "true" => Ok(Type::Bool(true)),
+                "false" => Ok(Type::Bool(false)),
+                _ => Err(failure()),
+            },
+            TypeTag::Null => Err(failure()),
+            TypeTag::List => Err(TypeError::Unsupported(format!(
+                "parsing {:?} as a list is not supported",
+                raw
+            ))),
+        }
+    }
+
+    pub fn tag(&self) -> TypeTag {
+        match self {
+            Type::Int(_) => TypeTag::Int,
+            Type::Decimal(_) => TypeTag::Decimal,
+            Type::VarChar(_) => TypeTag::VarChar,
+            Type::Json(_) => TypeTag::Json,
+            Type::List(_) => TypeTag::List,
+            Type::Bool(_) => TypeTag::Bool,
+            Type::Null => TypeTag::Null,
+        }
+    }
+
+    /// a list of elements of the same type
+    pub fn list(values: Vec<Type>) -> Result<Type, TypeError> {
+        if let Some(first) = values.first() {
+            if let Some(other) = values.iter().find(|value| value.rank() != first.rank()) {
+                return Err(TypeError::Mismatch(format!(
+                    "list of {} can't contain {}",
+                    first.kind(),
+                    other.kind()
+                )));
+            }
+        }
+        Ok(Type::List(values))
+    }
+
+    pub fn array_contains(&self, value: &Type) -> Result<bool, TypeError> {
+        match self {
+            Type::List(values) => Ok(values.contains(value)),
+            other => Err(TypeError::Mismatch(format!(
+                "array_contains of {} is not supported",
+                other.kind()
+            ))),
+        }
+    }
+
+    /// whether the text contains every term ignoring case
+    pub fn contains_terms(&self, terms: &[String]) -> Result<bool, TypeError> {
+        match self {
+            Type::VarChar(text) => {
+                let text = text.to_lowercase();
+                Ok(terms.iter().all(|term| text.contains(&term.to_lowercase())))
+            }
+            other => Err(TypeError::Mismatch(format!(
+                "text search in {} is not supported",
+                other.kind()
+            ))),
+        }
+    }
+
+    /// whether the whole text matches the SQL pattern, where `%` stands for any
+    /// characters, `_` for exactly one, and `\` makes the following character literal
+    pub fn like(&self, pattern: &str) -> Result<bool, TypeError> {
+        match self {
+            Type::VarChar(text) => {
+                let mut tokens = vec![];
+                let mut chars = pattern.chars();
+                while let Some(c) = chars.next() {
+                    tokens.push(match c {
+                        '%' => None,
+                        '_' => Some(None),
+                        '\\' => Some(Some(chars.next().unwrap_or('\\'))),
+                        c => Some(Some(c)),
+                    });
+                }
+                let text = text.chars().collect::<Vec<char>>();
+                // positions to resume from when the last `%` has to take one more character
+                let (mut t, mut p, mut resume) = (0, 0, None);
+                while t < text.len() {
+                    match tokens.get(p) {
+                        Some(Some(None)) => {
+                            t += 1;
+                            p += 1;
+                        }
+                        Some(Some(Some(c))) if *c == text[t] => {
+                            t += 1;
+                            p += 1;
+                        }
+                        Some(None) => {
+                            p += 1;
+                            resume = Some((t, p));
+                        }
+                        _ => match resume {
+                            Some((last, after)) => {
+                                t = last + 1;
+                                p = after;
+                                resume = Some((t, p));
+                            }
+                            None => return Ok(false),
+                        },
+                    }
+                }
+                Ok(tokens[p..].iter().all(Option::is_none))
+            }
+            other => Err(TypeError::Mismatch(format!(
+                "pattern matching in {} is not supported",
+                other.kind()
+            ))),
+        }
+    }
+
+    /// the value as JSON with `Int`s written in the given encoding
+    pub fn to_json(&self, encoding: IntEncoding) -> JsonValue {
+        match self {
+            Type::Int(value) => match encoding {
+                IntEncoding::Decimal => JsonValue::String(value.to_str_radix(10)),
+                IntEncoding::Hex => {
+                    let digits = value.to_str_radix(16);
+                    match digits.strip_prefix('-') {
+                        Some(digits) => JsonValue::String(format!("-0x{}", digits)),
+                        None => JsonValue::String(format!("0x{}", digits)),
+                    }
+                }
+                IntEncoding::Bytes => JsonValue::Array(
+                    value
+                        .to_signed_bytes_be()
+                        .into_iter()
+                        .map(JsonValue::from)
+                        .collect(),
+                ),
+            },
+            Type::Decimal(value) => JsonValue::String(value.to_string()),
+            Type::VarChar(value) => JsonValue::String(value.clone()),
+            Type::Json(value) => value.clone(),
+            Type::List(values) => {
+                JsonValue::Array(values.iter().map(|value| value.to_json(encoding)).collect())
+            }
+            Type::Bool(value) => JsonValue::Bool(*value),
+            Type::Null => JsonValue::Null,
+        }
+    }
+
+    /// extracts the value under `$.field[index]...` path, missing values are JSON nulls
+    pub fn json_extract(&self, path: &str) -> Result<Type, TypeError> {
+        let json = match self {
+            Type::Json(json) => json,
+            other => {
+                return Err(TypeError::Mismatch(format!(
+                    "json_extract of {} is not supported",
+                    other.kind()
+                )))
+            }
+        };
+        let invalid_path =
+            || TypeError::Unsupported(format!("{:?} is not a valid JSON path", path));
+        if !path.starts_with('$') {
+            return Err(invalid_path());
+        }
+        let mut value = json;
+        let mut rest = &path[1..];
+        while !rest.is_empty() {
+            if rest.starts_with('.') {
+                let end = rest[1..]
+                    .find(&['.', '['][..])
+                    .map(|end| end + 1)
+                    .unwrap_or_else(|| rest.len());
+                let key = &rest[1..end];
+                if key.is_empty() {
+                    return Err(invalid_path());
+                }
+                value = value.get(key).unwrap_or(&JsonValue::Null);
+                rest = &rest[end..];
+            } else if rest.starts_with('[') {
+                let end = rest.find(']').ok_or_else(invalid_path)?;
+                let index = rest[1..end]
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| invalid_path())?;
+                value = value.get(index).unwrap_or(&JsonValue::Null);
+                rest = &rest[end + 1..];
+            } else {
+                return Err(invalid_path());
+            }
+        }
+        Ok(match value {
+            JsonValue::Number(number) if number.is_i64() || number.is_u64() => {
+                Type::Int(BigInt::from_str(&number.to_string()).unwrap())
+            }
+            JsonValue::Number(number) => match BigDecimal::from_str(&number.to_string()) {
+                Ok(number) => Type::Decimal(number),
+                Err(_) => Type::Json(value.clone()),
+            },
+            JsonValue::String(string) => Type::VarChar(string.clone()),
+            value => Type::Json(value.clone()),
+        })
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Type::Int(_) => 0,
+            Type::Decimal(_) => 1,
+            Type::VarChar(_) => 2,
+            Type::Json(_) => 3,
+            Type::List(_) => 4,
+            Type::Bool(_) => 5,
+            Type::Null => 6,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Type::Int(_) => "Int",
+            Type::Decimal(_) => "Decimal",
+            Type::VarChar(_) => "VarChar",
+            Type::Json(_) => "Json",
+            Type::List(_) => "List",
+            Type::Bool(_) => "Bool",
+            Type::Null => "Null",
+        }
+    }
+
+    fn arithmetic(
+        &self,
+        other: &Type,
+        operator: &str,
+        int_op: fn(&BigInt, &BigInt) -> BigInt,
+        decimal_op: fn(&BigDecimal, &BigDecimal) -> BigDecimal,
+    ) -> Result<Type, TypeError> {
+        match (self, other) {
+            (Type::Null, _) | (_, Type::Null) => Ok(Type::Null),
+            (Type::Int(left), Type::Int(right)) => Ok(Type::Int(int_op(left, right))),
+            (Type::Decimal(left), Type::Decimal(right)) => {
+                Ok(Type::Decimal(decimal_op(left, right)))
+            }
+            (Type::Int(left), Type::Decimal(right)) => Ok(Type::Decimal(decimal_op(
+                &BigDecimal::from(left.clone()),
+                right,
+            ))),
+            (Type::Decimal(left), Type::Int(right)) => Ok(Type::Decimal(decimal_op(
+                left,
+                &BigDecimal::from(right.clone()),
+            ))),
+            (left, right) => Err(TypeError::Mismatch(format!(
+                "{} {} {} is not supported",
+                left.kind(),
+                operator,
+                right.kind()
+            ))),
+        }
+    }
+}
+
+impl Add for &Type {
+    type Output = Result<Type, TypeError>;
+
+    fn add(self, other: &Type) -> Self::Output {
+        self.arithmetic(other, "+", |l, r| l + r, |l, r| l + r)
+    }
+}
+
+impl Sub for &Type {
+    type Output = Result<Type, TypeError>;
+
+    fn sub(self, other: &Type) -> Self::Output {
+        self.arithmetic(other, "-", |l, r| l - r, |l, r| l - r)
+    }
+}
+
+impl Mul for &Type {
+    type Output = Result<Type, TypeError>;
+
+    fn mul(self, other: &Type) -> Self::Output {
+        self.arithmetic(other, "*", |l, r| l * r, |l, r| l * r)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,6 +498,11 @@ mod tests {
         fn from_ast() {
             assert_eq!(Type::try_from(int(100)), Ok(Type::Int(BigInt::from(100))))
         }
+
+        #[test]
+        fn to_ast() {
+            assert_eq!(AstTypeValue::from(Type::Int(BigInt::from(100))), int(100))
+        }
     }
 
     #[cfg(test)]
@@ -88,4 +530,459 @@ mod tests {
             )
         }
     }
+
+    #[cfg(test)]
+    mod hex_string_value {
+        use super::*;
+
+        #[test]
+        fn from_ast() {
+            assert_eq!(
+                Type::try_from(Value::HexStringLiteral("616263".to_owned())),
+                Ok(Type::VarChar("abc".to_owned()))
+            )
+        }
+
+        #[test]
+        fn invalid_utf8() {
+            assert_eq!(
+                Type::try_from(Value::HexStringLiteral("C328".to_owned())),
+                Err(TypeError::Encoding(
+                    "[195, 40] is not a valid UTF-8 string: invalid utf-8 sequence of 1 bytes from index 0"
+                        .to_owned()
+                ))
+            )
+        }
+
+        #[test]
+        fn invalid_hex() {
+            assert_eq!(
+                Type::try_from(Value::HexStringLiteral("ABC".to_owned())),
+                Err(TypeError::ParseFailure {
+                    raw: "X'ABC'".to_owned(),
+                    target: TypeTag::VarChar
+                })
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod boolean_value {
+        use super::*;
+
+        #[test]
+        fn from_literal() {
+            assert_eq!(Type::try_from(Value::Boolean(true)), Ok(Type::Bool(true)));
+            assert_eq!(Value::from(Type::Bool(false)), Value::Boolean(false));
+            assert_eq!(format!("{:?}", Type::Bool(true)), "Bool(true)");
+        }
+
+        #[test]
+        fn round_trip() {
+            for value in &[true, false] {
+                let encoded = serde_json::to_string(&Type::Bool(*value)).unwrap();
+                assert_eq!(
+                    serde_json::from_str::<Type>(&encoded).unwrap(),
+                    Type::Bool(*value)
+                );
+                assert_eq!(
+                    Type::parse(&value.to_string(), TypeTag::Bool),
+                    Ok(Type::Bool(*value))
+                );
+            }
+            assert_eq!(
+                Type::Bool(true).to_json(IntEncoding::Decimal),
+                JsonValue::Bool(true)
+            );
+        }
+
+        #[test]
+        fn ordered_after_other_types() {
+            assert!(Type::Bool(false) < Type::Bool(true));
+            assert!(Type::Int(BigInt::from(1)) < Type::Bool(false));
+        }
+    }
+
+    #[cfg(test)]
+    mod null_value {
+        use super::*;
+
+        #[test]
+        fn from_literal() {
+            assert_eq!(Type::try_from(Value::Null), Ok(Type::Null));
+            assert_eq!(Value::from(Type::Null), Value::Null);
+        }
+
+        #[test]
+        fn arithmetic_with_null() {
+            assert_eq!(&Type::Int(BigInt::from(1)) + &Type::Null, Ok(Type::Null));
+            assert_eq!(&Type::Null * &Type::Int(BigInt::from(1)), Ok(Type::Null));
+        }
+
+        #[test]
+        fn ordered_last() {
+            assert!(Type::Bool(true) < Type::Null);
+            assert!(Type::Int(BigInt::from(1)) < Type::Null);
+        }
+    }
+
+    #[cfg(test)]
+    mod parsing {
+        use super::*;
+
+        #[test]
+        fn numbers() {
+            assert_eq!(
+                Type::parse(" 42 ", TypeTag::Int),
+                Ok(Type::Int(BigInt::from(42)))
+            );
+            assert_eq!(
+                Type::parse("4.2", TypeTag::Decimal),
+                Ok(Type::Decimal(BigDecimal::new(BigInt::from(42), 1)))
+            );
+        }
+
+        #[test]
+        fn unparseable_number() {
+            assert_eq!(
+                Type::parse("4x2", TypeTag::Int),
+                Err(TypeError::ParseFailure {
+                    raw: "4x2".to_owned(),
+                    target: TypeTag::Int
+                })
+            );
+        }
+
+        #[test]
+        fn unsupported_literal() {
+            assert_eq!(
+                Type::try_from(Value::Date("2020-01-01".to_owned())),
+                Err(TypeError::Unsupported("Date(\"2020-01-01\")".to_owned()))
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod arithmetic {
+        use super::*;
+
+        fn int(value: i32) -> Type {
+            Type::Int(BigInt::from(value))
+        }
+
+        fn decimal(value: i32, scale: i64) -> Type {
+            Type::Decimal(BigDecimal::new(BigInt::from(value), scale))
+        }
+
+        fn string(value: &'static str) -> Type {
+            Type::VarChar(value.to_owned())
+        }
+
+        #[test]
+        fn add_ints() {
+            assert_eq!(&int(2) + &int(3), Ok(int(5)))
+        }
+
+        #[test]
+        fn add_decimals() {
+            assert_eq!(&decimal(15, 1) + &decimal(25, 1), Ok(decimal(40, 1)))
+        }
+
+        #[test]
+        fn add_int_to_decimal() {
+            assert_eq!(&int(1) + &decimal(15, 1), Ok(decimal(25, 1)));
+            assert_eq!(&decimal(15, 1) + &int(1), Ok(decimal(25, 1)))
+        }
+
+        #[test]
+        fn subtract_ints() {
+            assert_eq!(&int(2) - &int(3), Ok(int(-1)))
+        }
+
+        #[test]
+        fn subtract_decimals() {
+            assert_eq!(&decimal(25, 1) - &decimal(15, 1), Ok(decimal(10, 1)))
+        }
+
+        #[test]
+        fn subtract_int_and_decimal() {
+            assert_eq!(&int(3) - &decimal(15, 1), Ok(decimal(15, 1)));
+            assert_eq!(&decimal(35, 1) - &int(3), Ok(decimal(5, 1)))
+        }
+
+        #[test]
+        fn multiply_ints() {
+            assert_eq!(&int(2) * &int(3), Ok(int(6)))
+        }
+
+        #[test]
+        fn multiply_decimals() {
+            assert_eq!(&decimal(15, 1) * &decimal(2, 0), Ok(decimal(30, 1)))
+        }
+
+        #[test]
+        fn multiply_int_and_decimal() {
+            assert_eq!(&int(2) * &decimal(15, 1), Ok(decimal(30, 1)));
+            assert_eq!(&decimal(15, 1) * &int(2), Ok(decimal(30, 1)))
+        }
+
+        #[test]
+        fn add_string_and_int() {
+            assert_eq!(
+                &string("a") + &int(1),
+                Err(TypeError::Mismatch(
+                    "VarChar + Int is not supported".to_owned()
+                ))
+            )
+        }
+
+        #[test]
+        fn subtract_int_and_string() {
+            assert_eq!(
+                &int(1) - &string("a"),
+                Err(TypeError::Mismatch(
+                    "Int - VarChar is not supported".to_owned()
+                ))
+            )
+        }
+
+        #[test]
+        fn multiply_strings() {
+            assert_eq!(
+                &string("a") * &string("b"),
+                Err(TypeError::Mismatch(
+                    "VarChar * VarChar is not supported".to_owned()
+                ))
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod json {
+        use super::*;
+
+        fn document() -> Type {
+            Type::json(r#"{"user": {"name": "alex", "age": 30, "tags": ["a", "b"]}}"#).unwrap()
+        }
+
+        #[test]
+        fn invalid_json() {
+            assert_eq!(
+                Type::json("{"),
+                Err(TypeError::Mismatch(
+                    "\"{\" is not a valid JSON: EOF while parsing an object at line 1 column 1"
+                        .to_owned()
+                ))
+            )
+        }
+
+        #[test]
+        fn extract_nested_fields() {
+            assert_eq!(
+                document().json_extract("$.user.name"),
+                Ok(Type::VarChar("alex".to_owned()))
+            );
+            assert_eq!(
+                document().json_extract("$.user.age"),
+                Ok(Type::Int(BigInt::from(30)))
+            );
+            assert_eq!(
+                document().json_extract("$.user.tags[1]"),
+                Ok(Type::VarChar("b".to_owned()))
+            );
+            assert_eq!(
+                document().json_extract("$.user.tags"),
+                Ok(Type::json(r#"["a", "b"]"#).unwrap())
+            );
+        }
+
+        #[test]
+        fn extract_missing_field() {
+            assert_eq!(
+                document().json_extract("$.user.email"),
+                Ok(Type::Json(JsonValue::Null))
+            )
+        }
+
+        #[test]
+        fn extract_from_non_json() {
+            assert_eq!(
+                Type::VarChar("{}".to_owned()).json_extract("$.a"),
+                Err(TypeError::Mismatch(
+                    "json_extract of VarChar is not supported".to_owned()
+                ))
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod text_search {
+        use super::*;
+
+        fn terms(terms: &[&str]) -> Vec<String> {
+            terms.iter().map(|term| (*term).to_owned()).collect()
+        }
+
+        #[test]
+        fn contains_all_terms() {
+            let text = Type::VarChar("Foo and Bar".to_owned());
+
+            assert_eq!(text.contains_terms(&terms(&["bar", "foo"])), Ok(true));
+            assert_eq!(text.contains_terms(&terms(&["foo", "baz"])), Ok(false));
+        }
+
+        #[test]
+        fn search_in_non_string() {
+            assert_eq!(
+                Type::Int(BigInt::from(1)).contains_terms(&terms(&["1"])),
+                Err(TypeError::Mismatch(
+                    "text search in Int is not supported".to_owned()
+                ))
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod pattern_matching {
+        use super::*;
+
+        fn like(text: &str, pattern: &str) -> bool {
+            Type::VarChar(text.to_owned()).like(pattern).unwrap()
+        }
+
+        #[test]
+        fn prefix_and_suffix() {
+            assert!(like("apple", "a%"));
+            assert!(!like("banana", "a%"));
+            assert!(like("banana", "%na"));
+            assert!(!like("bananas", "%na"));
+        }
+
+        #[test]
+        fn contains() {
+            assert!(like("pineapple", "%eap%"));
+            assert!(like("eap", "%eap%"));
+            assert!(!like("pear", "%eap%"));
+            assert!(like("", "%"));
+        }
+
+        #[test]
+        fn single_character() {
+            assert!(like("cat", "c_t"));
+            assert!(!like("coat", "c_t"));
+            assert!(like("cart", "c%_t"));
+            assert!(!like("ct", "c_t"));
+        }
+
+        #[test]
+        fn escaped_wildcards() {
+            assert!(like("100%", "100\\%"));
+            assert!(!like("1000", "100\\%"));
+            assert!(like("a_b", "a\\_b"));
+            assert!(!like("axb", "a\\_b"));
+        }
+
+        #[test]
+        fn match_non_string() {
+            assert_eq!(
+                Type::Int(BigInt::from(1)).like("1"),
+                Err(TypeError::Mismatch(
+                    "pattern matching in Int is not supported".to_owned()
+                ))
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod list {
+        use super::*;
+
+        fn int(value: i32) -> Type {
+            Type::Int(BigInt::from(value))
+        }
+
+        #[test]
+        fn serialization_round_trip() {
+            let list = Type::list(vec![int(1), int(2), int(3)]).unwrap();
+
+            let serialized = serde_json::to_string(&list).unwrap();
+
+            assert_eq!(serde_json::from_str::<Type>(&serialized).unwrap(), list)
+        }
+
+        #[test]
+        fn elements_of_different_types() {
+            assert_eq!(
+                Type::list(vec![int(1), Type::VarChar("2".to_owned())]),
+                Err(TypeError::Mismatch(
+                    "list of Int can't contain VarChar".to_owned()
+                ))
+            )
+        }
+
+        #[test]
+        fn contains() {
+            let list = Type::list(vec![int(1), int(2), int(3)]).unwrap();
+
+            assert_eq!(list.array_contains(&int(2)), Ok(true));
+            assert_eq!(list.array_contains(&int(4)), Ok(false));
+        }
+    }
+
+    #[cfg(test)]
+    mod int_encoding {
+        use super::*;
+
+        fn int(value: i64) -> Type {
+            Type::Int(BigInt::from(value))
+        }
+
+        #[test]
+        fn decimal_by_default() {
+            assert_eq!(
+                int(255).to_json(IntEncoding::default()),
+                JsonValue::String("255".to_owned())
+            );
+        }
+
+        #[test]
+        fn overflowing_json_number() {
+            let value = BigInt::from_str("123456789012345678901234567890").unwrap();
+
+            assert_eq!(
+                Type::Int(value).to_json(IntEncoding::Decimal),
+                JsonValue::String("123456789012345678901234567890".to_owned())
+            );
+        }
+
+        #[test]
+        fn hex() {
+            assert_eq!(
+                int(255).to_json(IntEncoding::Hex),
+                JsonValue::String("0xff".to_owned())
+            );
+            assert_eq!(
+                int(-255).to_json(IntEncoding::Hex),
+                JsonValue::String("-0xff".to_owned())
+            );
+        }
+
+        #[test]
+        fn bytes() {
+            assert_eq!(
+                int(255).to_json(IntEncoding::Bytes),
+                serde_json::json!([0, 255])
+            );
+        }
+
+        #[test]
+        fn ints_in_list() {
+            let list = Type::list(vec![int(10), int(11)]).unwrap();
+
+            assert_eq!(
+                list.to_json(IntEncoding::Hex),
+                serde_json::json!(["0xa", "0xb"])
+            );
+        }
+    }
 }